@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+
+use crate::quality::QualitySettings;
+
+/// Card background alpha when transparency is reduced; the photo behind is
+/// only a faint hint
+const REDUCED_TRANSPARENCY_OPACITY: f64 = 0.92;
+
+/// Card background alpha in high-contrast mode
+const HIGH_CONTRAST_OPACITY: f64 = 0.85;
+
+/// User preferences the renderer must honour, usually read from the
+/// `prefers-reduced-motion`, `prefers-reduced-transparency` and
+/// `prefers-contrast` media queries
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct AccessibilityConfig {
+    pub(crate) reduced_motion: bool,
+    pub(crate) reduced_transparency: bool,
+    pub(crate) high_contrast: bool,
+}
+
+impl AccessibilityConfig {
+    /// Narrows the tier's effects to what the preferences allow
+    ///
+    /// Reduced motion stops everything that moves with scroll or pointer:
+    /// parallax, magnetic hover, Ken Burns, and the scroll-linked blur ramp,
+    /// which holds at its resting radius. Scroll timelines and springs aren't
+    /// tier settings and check `reduced_motion` themselves: timelines hold
+    /// their first keyframe values and springs jump straight to their
+    /// targets. Reduced transparency and high contrast both make the card
+    /// close to opaque; with reduced transparency there is also nothing left
+    /// to blur.
+    pub(crate) fn apply(&self, mut settings: QualitySettings) -> QualitySettings {
+        if self.reduced_motion {
+            settings.parallax_layers = 0;
+            settings.magnetic_hover = false;
+            settings.ken_burns = false;
+            settings.blur_max = settings.blur_min;
+        }
+        if self.high_contrast {
+            settings.card_opacity = settings.card_opacity.max(HIGH_CONTRAST_OPACITY);
+        }
+        if self.reduced_transparency {
+            settings.card_opacity = settings.card_opacity.max(REDUCED_TRANSPARENCY_OPACITY);
+            settings.blur_min = 0.0;
+            settings.blur_max = 0.0;
+        }
+        settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quality::QualityTier;
+
+    #[test]
+    fn reduced_motion_stops_moving_effects() {
+        let config = AccessibilityConfig {
+            reduced_motion: true,
+            ..AccessibilityConfig::default()
+        };
+        let settings = config.apply(QualityTier::High.settings());
+        assert_eq!(settings.parallax_layers, 0);
+        assert!(!settings.magnetic_hover && !settings.ken_burns);
+        assert_eq!((settings.blur_min, settings.blur_max), (3.0, 3.0));
+        assert_eq!(settings.card_opacity, QualityTier::High.settings().card_opacity);
+    }
+
+    #[test]
+    fn transparency_and_contrast_make_the_card_opaque() {
+        let reduced = AccessibilityConfig {
+            reduced_transparency: true,
+            high_contrast: true,
+            ..AccessibilityConfig::default()
+        };
+        let settings = reduced.apply(QualityTier::Medium.settings());
+        assert_eq!(settings.card_opacity, REDUCED_TRANSPARENCY_OPACITY);
+        assert_eq!((settings.blur_min, settings.blur_max), (0.0, 0.0));
+        // Motion is untouched
+        assert_eq!(settings.parallax_layers, 2);
+
+        let contrast = AccessibilityConfig {
+            high_contrast: true,
+            ..AccessibilityConfig::default()
+        };
+        assert_eq!(contrast.apply(QualityTier::Low.settings()).card_opacity, HIGH_CONTRAST_OPACITY);
+        assert_eq!(AccessibilityConfig::default().apply(QualityTier::Low.settings()), QualityTier::Low.settings());
+    }
+}
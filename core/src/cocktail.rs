@@ -0,0 +1,455 @@
+use serde::{Deserialize, Serialize};
+
+/// Measure a spec quantity is given in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SpecUnit {
+    Oz,
+    Ml,
+    Cl,
+    Barspoon,
+    Dash,
+    Drop,
+    Teaspoon,
+    Tablespoon,
+    Splash,
+    Part,
+}
+
+impl SpecUnit {
+    fn parse(word: &str) -> Option<SpecUnit> {
+        let unit = match word.trim_end_matches('.').to_lowercase().as_str() {
+            "oz" | "ozs" | "ounce" | "ounces" => SpecUnit::Oz,
+            "ml" | "mls" => SpecUnit::Ml,
+            "cl" | "cls" => SpecUnit::Cl,
+            "barspoon" | "barspoons" | "bsp" | "bsps" | "b/s" => SpecUnit::Barspoon,
+            "dash" | "dashes" => SpecUnit::Dash,
+            "drop" | "drops" => SpecUnit::Drop,
+            "tsp" | "tsps" | "teaspoon" | "teaspoons" => SpecUnit::Teaspoon,
+            "tbsp" | "tbsps" | "tablespoon" | "tablespoons" => SpecUnit::Tablespoon,
+            "splash" | "splashes" => SpecUnit::Splash,
+            "part" | "parts" => SpecUnit::Part,
+            _ => return None,
+        };
+        Some(unit)
+    }
+
+    /// Millilitres per unit, for costing; splashes and parts are not fixed
+    fn millilitres(self) -> Option<f64> {
+        match self {
+            SpecUnit::Oz => Some(29.5735),
+            SpecUnit::Ml => Some(1.0),
+            SpecUnit::Cl => Some(10.0),
+            SpecUnit::Barspoon => Some(5.0),
+            SpecUnit::Dash => Some(0.92),
+            SpecUnit::Drop => Some(0.05),
+            SpecUnit::Teaspoon => Some(4.93),
+            SpecUnit::Tablespoon => Some(14.79),
+            SpecUnit::Splash | SpecUnit::Part => None,
+        }
+    }
+}
+
+/// How an ingredient goes into the drink when it isn't simply measured in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SpecMethod {
+    Top,
+    Float,
+    Rinse,
+    Muddle,
+}
+
+/// One line of a cocktail spec
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct SpecIngredient {
+    pub(crate) quantity: Option<f64>,
+    pub(crate) unit: Option<SpecUnit>,
+    pub(crate) ingredient: String,
+    pub(crate) brand: Option<String>,
+    /// Quantity converted to millilitres when the unit has a fixed volume
+    pub(crate) volume_ml: Option<f64>,
+    pub(crate) method: Option<SpecMethod>,
+    pub(crate) garnish: bool,
+}
+
+/// A parsed spec; matches the `ingredients` and `instructions` columns of
+/// the `cocktails` table
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct CocktailSpec {
+    pub(crate) ingredients: Vec<SpecIngredient>,
+    pub(crate) instructions: Vec<String>,
+}
+
+/// Brands bartenders name in specs ahead of the generic ingredient
+const BRANDS: &[&str] = &[
+    "angostura",
+    "peychaud's",
+    "regans'",
+    "bittermens",
+    "fee brothers",
+    "luxardo",
+    "del maguey",
+    "fever-tree",
+    "fever tree",
+    "q mixers",
+    "dolin",
+    "tanqueray",
+    "beefeater",
+    "hendrick's",
+    "plymouth",
+    "bulleit",
+    "rittenhouse",
+    "wild turkey",
+    "buffalo trace",
+    "maker's mark",
+    "espolon",
+    "fortaleza",
+    "siete leguas",
+    "smith & cross",
+    "plantation",
+    "appleton",
+    "monin",
+    "giffard",
+    "st-germain",
+    "st germain",
+    "grand marnier",
+    "fernet-branca",
+    "fernet branca",
+];
+
+/// Verbs that start a preparation step rather than an ingredient
+const STEPS: &[&str] = &[
+    "shake", "stir", "strain", "double", "fine", "dry", "build", "serve", "pour", "blend", "whip", "swizzle", "express",
+    "chill", "rim",
+];
+
+/// Techniques that mark a clause as a direction wherever they appear
+const TECHNIQUES: &[&str] = &["shake", "shaken", "stir", "stirred", "strain", "strained", "blend", "blended", "swizzle"];
+
+/// Parses a spec such as "2 oz mezcal, ¾ oz lime, 0.5 oz agave, 2 dashes mole
+/// bitters, top with soda, garnish: lime wheel"
+///
+/// Clauses split on commas, semicolons and new lines (outside parentheses).
+/// Quantities may be decimals, ASCII or unicode fractions, mixed numbers or
+/// ranges, which are averaged.
+pub(crate) fn parse_spec(spec: &str) -> CocktailSpec {
+    let mut parsed = CocktailSpec::default();
+    for clause in split_clauses(spec) {
+        let clause = clause.trim().trim_start_matches(['-', '•', '*']).trim();
+        if clause.is_empty() {
+            continue;
+        }
+        match parse_ingredient(clause) {
+            Some(ingredient) => parsed.ingredients.push(ingredient),
+            None => parsed.instructions.push(clause.to_string()),
+        }
+    }
+    parsed
+}
+
+/// Parses one clause, or returns `None` for a preparation step
+fn parse_ingredient(clause: &str) -> Option<SpecIngredient> {
+    let normalized = normalize_fractions(clause);
+    let mut words: Vec<&str> = normalized.split_whitespace().collect();
+    let mut entry = SpecIngredient::default();
+
+    // Leading method: "top with soda", "float of overproof rum", "garnish: mint"
+    let lower: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+    let lead = lower.first().map(|w| w.trim_end_matches(':')).unwrap_or_default();
+    let method = match lead {
+        "top" | "topped" => Some(SpecMethod::Top),
+        "float" | "floated" => Some(SpecMethod::Float),
+        "rinse" | "rinsed" => Some(SpecMethod::Rinse),
+        "muddle" | "muddled" => Some(SpecMethod::Muddle),
+        _ => None,
+    };
+    if method.is_some() || lead == "garnish" || lead == "garnished" {
+        entry.method = method;
+        entry.garnish = method.is_none();
+        words.remove(0);
+        if words.first().is_some_and(|w| matches!(w.to_lowercase().as_str(), "with" | "of" | "up")) {
+            words.remove(0);
+        }
+    } else if STEPS.contains(&lead) {
+        return None;
+    }
+
+    // Quantity and unit: "2 oz", "1.5oz", "2-3 dashes", "30ml"
+    if let Some((quantity, consumed)) = words.first().and_then(|w| parse_quantity(w, words.get(1).copied())) {
+        entry.quantity = Some(quantity);
+        words.drain(..consumed);
+        if let Some(unit) = words.first().and_then(|w| SpecUnit::parse(w)) {
+            entry.unit = Some(unit);
+            words.remove(0);
+            if words.first().is_some_and(|w| w.eq_ignore_ascii_case("of")) {
+                words.remove(0);
+            }
+        }
+    } else if let Some((quantity, unit)) = words.first().and_then(|w| split_glued_unit(w)) {
+        entry.quantity = Some(quantity);
+        entry.unit = Some(unit);
+        words.remove(0);
+    }
+
+    // "3 muddled strawberries"
+    if words.first().is_some_and(|w| w.eq_ignore_ascii_case("muddled")) {
+        entry.method.get_or_insert(SpecMethod::Muddle);
+        words.remove(0);
+    }
+
+    // Trailing method and garnish words: "lime wheel garnish", "mint, muddled"
+    while let Some(last) = words.last().map(|w| w.to_lowercase()) {
+        match last.trim_end_matches(['.', ')']) {
+            "garnish" => entry.garnish = true,
+            "muddled" => {
+                entry.method.get_or_insert(SpecMethod::Muddle);
+            }
+            "float" | "floated" => {
+                entry.method.get_or_insert(SpecMethod::Float);
+            }
+            "rinse" => {
+                entry.method.get_or_insert(SpecMethod::Rinse);
+            }
+            // "(for garnish)", "to garnish", "as garnish"
+            "for" | "(for" | "to" | "as" if entry.garnish => {}
+            _ => break,
+        }
+        words.pop();
+    }
+
+    let text = words.join(" ");
+    let (ingredient, brand) = split_brand(&text);
+    if ingredient.is_empty() {
+        return None;
+    }
+    if entry.quantity.is_none() && entry.method.is_none() && !entry.garnish {
+        // Unmeasured sentences and anything mentioning a technique are
+        // directions, not ingredients: "then shake with ice"
+        let technique = lower.iter().any(|w| TECHNIQUES.contains(&w.trim_end_matches(['.', ','])));
+        if technique || text.ends_with('.') {
+            return None;
+        }
+    }
+
+    entry.ingredient = ingredient;
+    entry.brand = brand;
+    entry.volume_ml = match (entry.quantity, entry.unit.and_then(SpecUnit::millilitres)) {
+        (Some(quantity), Some(ml)) => Some((quantity * ml * 100.0).round() / 100.0),
+        _ => None,
+    };
+    Some(entry)
+}
+
+/// Parses "2", "0.75", "3/4", "1 1/2", "1-1/2" and "2-3"; returns the value and
+/// how many words it used
+fn parse_quantity(word: &str, next: Option<&str>) -> Option<(f64, usize)> {
+    let value = parse_number(word)?;
+    // Mixed number split across words: "1 1/2"
+    if let Some(fraction) = next.filter(|n| n.contains('/')).and_then(parse_fraction) {
+        if value.fract() == 0.0 {
+            return Some((value + fraction, 2));
+        }
+    }
+    Some((value, 1))
+}
+
+fn parse_number(word: &str) -> Option<f64> {
+    if let Some(value) = parse_finite(word) {
+        return Some(value);
+    }
+    if let Some(fraction) = parse_fraction(word) {
+        return Some(fraction);
+    }
+    let (left, right) = word.split_once('-')?;
+    let left = parse_finite(left)?;
+    // "1-1/2" is a mixed number, "2-3" a range
+    match parse_fraction(right) {
+        Some(fraction) if right.contains('/') => Some(left + fraction),
+        _ => parse_finite(right).map(|right| (left + right) / 2.0),
+    }
+}
+
+fn parse_fraction(word: &str) -> Option<f64> {
+    let (numerator, denominator) = word.split_once('/')?;
+    let numerator = parse_finite(numerator)?;
+    let denominator = parse_finite(denominator)?;
+    (denominator != 0.0).then(|| numerator / denominator)
+}
+
+/// Parses a plain number, turning away "inf", "nan" and overflowing
+/// exponents that `f64::from_str` would otherwise accept
+fn parse_finite(word: &str) -> Option<f64> {
+    word.parse::<f64>().ok().filter(|value| value.is_finite())
+}
+
+/// Splits "30ml" or "1.5oz" into quantity and unit
+fn split_glued_unit(word: &str) -> Option<(f64, SpecUnit)> {
+    let split = word.find(|c: char| c.is_alphabetic())?;
+    let (number, unit) = word.split_at(split);
+    Some((parse_number(number)?, SpecUnit::parse(unit)?))
+}
+
+/// Rewrites unicode fractions as ASCII so "1½" reads as "1 1/2" and "¾" as "3/4"
+fn normalize_fractions(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        let fraction = match c {
+            '¼' => "1/4",
+            '½' => "1/2",
+            '¾' => "3/4",
+            '⅓' => "1/3",
+            '⅔' => "2/3",
+            '⅕' => "1/5",
+            '⅖' => "2/5",
+            '⅗' => "3/5",
+            '⅘' => "4/5",
+            '⅙' => "1/6",
+            '⅚' => "5/6",
+            '⅛' => "1/8",
+            '⅜' => "3/8",
+            '⅝' => "5/8",
+            '⅞' => "7/8",
+            '⁄' => {
+                normalized.push('/');
+                continue;
+            }
+            _ => {
+                normalized.push(c);
+                continue;
+            }
+        };
+        if normalized.ends_with(|c: char| c.is_ascii_digit()) {
+            normalized.push(' ');
+        }
+        normalized.push_str(fraction);
+        normalized.push(' ');
+    }
+    normalized
+}
+
+/// Separates a brand from the generic ingredient: "mezcal (Del Maguey Vida)"
+/// or "Angostura bitters"
+fn split_brand(text: &str) -> (String, Option<String>) {
+    let text = text.trim().trim_end_matches('.').trim();
+    if let (Some(open), true) = (text.find('('), text.ends_with(')')) {
+        let brand = text[open + 1..text.len() - 1].trim();
+        let ingredient = text[..open].trim();
+        if !brand.is_empty() && !ingredient.is_empty() {
+            return (ingredient.to_string(), Some(brand.to_string()));
+        }
+    }
+
+    let lower = text.to_lowercase();
+    for brand in BRANDS {
+        let Some(rest) = lower.strip_prefix(brand) else {
+            continue;
+        };
+        if !rest.is_empty() && !rest.starts_with(' ') {
+            continue;
+        }
+        let brand_text = text[..brand.len()].to_string();
+        let ingredient = text[brand.len()..].trim();
+        if ingredient.is_empty() {
+            // The brand is the product: "1 oz Aperol"
+            return (brand_text.clone(), Some(brand_text));
+        }
+        return (ingredient.to_string(), Some(brand_text));
+    }
+
+    (text.to_string(), None)
+}
+
+/// Splits on commas, semicolons and new lines that sit outside parentheses
+fn split_clauses(spec: &str) -> Vec<&str> {
+    let mut clauses = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in spec.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' | ';' | '\n' if depth == 0 => {
+                clauses.push(&spec[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    clauses.push(&spec[start..]);
+    clauses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ingredient(clause: &str) -> SpecIngredient {
+        parse_ingredient(clause).unwrap_or_else(|| panic!("{clause:?} read as a step"))
+    }
+
+    #[test]
+    fn fractions_mixed_numbers_and_ranges() {
+        assert_eq!(ingredient("¾ oz lime").quantity, Some(0.75));
+        assert_eq!(ingredient("1½ oz rye").quantity, Some(1.5));
+        assert_eq!(ingredient("1 1/2 oz rye").quantity, Some(1.5));
+        assert_eq!(ingredient("1-1/2 oz rye").quantity, Some(1.5));
+        assert_eq!(ingredient("1⁄4 oz maraschino").quantity, Some(0.25));
+        assert_eq!(ingredient("2-3 dashes bitters").quantity, Some(2.5));
+
+        let glued = ingredient("30ml gin");
+        assert_eq!((glued.quantity, glued.unit, glued.volume_ml), (Some(30.0), Some(SpecUnit::Ml), Some(30.0)));
+        assert_eq!(ingredient("2 oz mezcal").volume_ml, Some(59.15));
+    }
+
+    #[test]
+    fn non_finite_numbers_are_not_quantities() {
+        for word in ["inf", "nan", "infinity", "-inf", "1e400"] {
+            assert_eq!(parse_number(word), None, "{word}");
+        }
+        assert_eq!(parse_number("inf/2"), None);
+        assert_eq!(parse_number("1-nan"), None);
+
+        let entry = ingredient("inf oz gin");
+        assert_eq!((entry.quantity, entry.unit), (None, None));
+        assert_eq!(entry.ingredient, "inf oz gin");
+    }
+
+    #[test]
+    fn brands_split_from_the_ingredient() {
+        let entry = ingredient("2 dashes Angostura bitters");
+        assert_eq!(entry.ingredient, "bitters");
+        assert_eq!(entry.brand.as_deref(), Some("Angostura"));
+
+        let entry = ingredient("2 oz mezcal (Del Maguey Vida)");
+        assert_eq!(entry.ingredient, "mezcal");
+        assert_eq!(entry.brand.as_deref(), Some("Del Maguey Vida"));
+
+        let entry = ingredient("1 oz Luxardo");
+        assert_eq!((entry.ingredient.as_str(), entry.brand.as_deref()), ("Luxardo", Some("Luxardo")));
+        // A brand has to be a whole word
+        assert_eq!(ingredient("1 oz plymouthish gin").brand, None);
+    }
+
+    #[test]
+    fn top_float_and_garnish() {
+        let top = ingredient("top with soda");
+        assert_eq!((top.method, top.ingredient.as_str(), top.quantity), (Some(SpecMethod::Top), "soda", None));
+
+        let float = ingredient("0.5 oz overproof rum float");
+        assert_eq!((float.method, float.quantity), (Some(SpecMethod::Float), Some(0.5)));
+
+        let garnish = ingredient("garnish: lime wheel");
+        assert!(garnish.garnish);
+        assert_eq!((garnish.method, garnish.ingredient.as_str()), (None, "lime wheel"));
+        assert!(ingredient("orange twist (for garnish)").garnish);
+    }
+
+    #[test]
+    fn spec_separates_ingredients_from_steps() {
+        let spec = parse_spec("2 oz mezcal, ¾ oz lime; 2 dashes mole bitters\nShake with ice, double strain.");
+        let names: Vec<&str> = spec.ingredients.iter().map(|i| i.ingredient.as_str()).collect();
+        assert_eq!(names, ["mezcal", "lime", "mole bitters"]);
+        assert_eq!(spec.instructions, ["Shake with ice", "double strain."]);
+    }
+}
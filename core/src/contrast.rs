@@ -0,0 +1,378 @@
+use serde::{Deserialize, Serialize};
+
+/// WCAG 2 minimum contrast ratios
+const AA_NORMAL: f64 = 4.5;
+const AA_LARGE: f64 = 3.0;
+const AAA_NORMAL: f64 = 7.0;
+const AAA_LARGE: f64 = 4.5;
+
+/// Opacity steps searched when suggesting a card tint or shadow
+const OPACITY_STEP: f64 = 0.01;
+
+/// Blur radius suggested for a text-shadow halo
+const SHADOW_BLUR_PX: u32 = 4;
+
+/// sRGB colour with alpha, channels 0–255 and alpha 0–1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Rgba {
+    pub(crate) r: f64,
+    pub(crate) g: f64,
+    pub(crate) b: f64,
+    pub(crate) a: f64,
+}
+
+impl Rgba {
+    const BLACK: Rgba = Rgba::opaque(0.0, 0.0, 0.0);
+    const WHITE: Rgba = Rgba::opaque(255.0, 255.0, 255.0);
+
+    const fn opaque(r: f64, g: f64, b: f64) -> Rgba {
+        Rgba { r, g, b, a: 1.0 }
+    }
+
+    /// Parses "#d1a054", "#fff", "rgb(209, 160, 84)" or "rgba(255, 255, 255, 0.05)"
+    pub(crate) fn parse(text: &str) -> Result<Rgba, String> {
+        let text = text.trim().to_lowercase();
+        let invalid = || format!("Invalid colour \"{}\"", text);
+
+        if let Some(hex) = text.strip_prefix('#') {
+            let digits: Vec<u32> = hex.chars().map(|c| c.to_digit(16)).collect::<Option<_>>().ok_or_else(invalid)?;
+            let channel = |i: usize| f64::from(digits[i] * 16 + digits[i + 1]);
+            return match digits.len() {
+                3 => Ok(Rgba::opaque(
+                    f64::from(digits[0] * 17),
+                    f64::from(digits[1] * 17),
+                    f64::from(digits[2] * 17),
+                )),
+                6 => Ok(Rgba::opaque(channel(0), channel(2), channel(4))),
+                8 => Ok(Rgba {
+                    a: channel(6) / 255.0,
+                    ..Rgba::opaque(channel(0), channel(2), channel(4))
+                }),
+                _ => Err(invalid()),
+            };
+        }
+
+        let inner = text
+            .strip_prefix("rgba")
+            .or_else(|| text.strip_prefix("rgb"))
+            .and_then(|rest| rest.trim_start().strip_prefix('('))
+            .and_then(|rest| rest.strip_suffix(')'))
+            .ok_or_else(invalid)?;
+        let values: Vec<f64> = inner
+            .split([',', ' ', '/'])
+            .filter(|v| !v.is_empty())
+            .map(|v| v.parse::<f64>().ok().filter(|n| n.is_finite()))
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
+        match *values.as_slice() {
+            [r, g, b] => Ok(Rgba::opaque(r, g, b)),
+            [r, g, b, a] => Ok(Rgba { r, g, b, a: a.clamp(0.0, 1.0) }),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Grey with the given relative luminance
+    fn grey(luminance: f64) -> Rgba {
+        let value = encode(luminance.clamp(0.0, 1.0)) * 255.0;
+        Rgba::opaque(value, value, value)
+    }
+
+    /// This colour at `alpha` painted over an opaque `background`, blended in
+    /// sRGB as browsers do
+    fn over(self, alpha: f64, background: Rgba) -> Rgba {
+        let mix = |top: f64, bottom: f64| top * alpha + bottom * (1.0 - alpha);
+        Rgba::opaque(mix(self.r, background.r), mix(self.g, background.g), mix(self.b, background.b))
+    }
+
+    /// WCAG relative luminance of the colour's RGB channels
+    fn luminance(self) -> f64 {
+        0.2126 * decode(self.r / 255.0) + 0.7152 * decode(self.g / 255.0) + 0.0722 * decode(self.b / 255.0)
+    }
+
+    fn css(self, alpha: f64) -> String {
+        format!(
+            "rgba({}, {}, {}, {})",
+            self.r.round(),
+            self.g.round(),
+            self.b.round(),
+            round(alpha)
+        )
+    }
+
+    fn hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r.round() as u8, self.g.round() as u8, self.b.round() as u8)
+    }
+}
+
+/// sRGB channel (0–1) to linear light
+fn decode(channel: f64) -> f64 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Linear light to sRGB channel (0–1)
+fn encode(linear: f64) -> f64 {
+    if linear <= 0.0031308 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn contrast_ratio(a: f64, b: f64) -> f64 {
+    let (light, dark) = if a > b { (a, b) } else { (b, a) };
+    (light + 0.05) / (dark + 0.05)
+}
+
+/// Card opacity that would reach a target, using a fixed tint colour
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct TintSuggestion {
+    pub(crate) color: String,
+    pub(crate) opacity: f64,
+    /// Ready-made `background-color` value
+    pub(crate) css: String,
+}
+
+/// Halo behind the glyphs that would reach a target
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ShadowSuggestion {
+    pub(crate) color: String,
+    pub(crate) opacity: f64,
+    /// Ready-made `text-shadow` value
+    pub(crate) css: String,
+}
+
+/// Cheapest changes that would meet one WCAG level
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ContrastFix {
+    pub(crate) target_ratio: f64,
+    pub(crate) passes: bool,
+    /// Lowest opacity of the current card tint that passes, if any does; may
+    /// be below the current opacity when there is headroom
+    pub(crate) card_opacity: Option<f64>,
+    /// Black or white tint, whichever contrasts more with the text, when it
+    /// differs from the current tint; light text on a white tint can only
+    /// pass with a dark one
+    pub(crate) contrasting_tint: Option<TintSuggestion>,
+    pub(crate) text_shadow: Option<ShadowSuggestion>,
+}
+
+/// Worst-case contrast of text on a glass card over a varying background
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ContrastReport {
+    pub(crate) worst_ratio: f64,
+    /// Background luminance behind the card where the worst ratio occurs
+    pub(crate) worst_background_luminance: f64,
+    pub(crate) aa: ContrastFix,
+    pub(crate) aaa: ContrastFix,
+}
+
+/// Checks text on a translucent card against every background luminance
+/// present in `histogram`
+///
+/// `histogram` holds pixel counts for evenly sized luminance bins from 0.0 to
+/// 1.0, sampled from the (blurred) photo behind the card; empty bins are
+/// ignored and each occupied bin is tested at both edges and its middle. The
+/// background is treated as grey of that luminance, which is what the text
+/// contrasts against once the card's tint is blended over it. Text is taken
+/// as opaque.
+pub(crate) fn check_contrast(text: Rgba, card: Rgba, histogram: &[f64], large_text: bool) -> Result<ContrastReport, String> {
+    let backgrounds = background_luminances(histogram);
+    if backgrounds.is_empty() {
+        return Err("Background histogram has no samples".to_string());
+    }
+
+    let text_luminance = text.luminance();
+    let (worst_ratio, worst_background) = worst_case(text_luminance, card, card.a, &backgrounds);
+
+    let (aa, aaa) = if large_text { (AA_LARGE, AAA_LARGE) } else { (AA_NORMAL, AAA_NORMAL) };
+    let fix = |target: f64| suggest(text, card, &backgrounds, worst_ratio, target);
+
+    Ok(ContrastReport {
+        worst_ratio: round(worst_ratio),
+        worst_background_luminance: round(worst_background),
+        aa: fix(aa),
+        aaa: fix(aaa),
+    })
+}
+
+fn suggest(text: Rgba, card: Rgba, backgrounds: &[f64], worst_ratio: f64, target: f64) -> ContrastFix {
+    let text_luminance = text.luminance();
+    let passes = worst_ratio >= target;
+
+    let card_opacity = min_opacity(|alpha| worst_case(text_luminance, card, alpha, backgrounds).0 >= target);
+
+    // Black against white text, white against dark text
+    let contrasting = if contrast_ratio(text_luminance, 0.0) >= contrast_ratio(text_luminance, 1.0) {
+        Rgba::BLACK
+    } else {
+        Rgba::WHITE
+    };
+    let contrasting_tint = if passes || contrasting.hex() == card.hex() {
+        None
+    } else {
+        min_opacity(|alpha| worst_case(text_luminance, contrasting, alpha, backgrounds).0 >= target).map(|opacity| {
+            TintSuggestion {
+                color: contrasting.hex(),
+                opacity,
+                css: contrasting.css(opacity),
+            }
+        })
+    };
+
+    // A halo sits between the glyphs and the card, so it is blended over the
+    // card's worst-case colour
+    let text_shadow = if passes {
+        None
+    } else {
+        let composites: Vec<Rgba> = backgrounds.iter().map(|&l| card.over(card.a, Rgba::grey(l))).collect();
+        min_opacity(|alpha| {
+            composites
+                .iter()
+                .all(|&base| contrast_ratio(text_luminance, contrasting.over(alpha, base).luminance()) >= target)
+        })
+        .map(|opacity| ShadowSuggestion {
+            color: contrasting.hex(),
+            opacity,
+            css: format!("0 0 {}px {}", SHADOW_BLUR_PX, contrasting.css(opacity)),
+        })
+    };
+
+    ContrastFix {
+        target_ratio: target,
+        passes,
+        card_opacity,
+        contrasting_tint,
+        text_shadow,
+    }
+}
+
+/// Lowest opacity from 0.0 up to 1.0, in 0.01 steps, that passes
+fn min_opacity(passes: impl Fn(f64) -> bool) -> Option<f64> {
+    let last = (1.0 / OPACITY_STEP).round() as u32;
+    (0..=last)
+        .map(|step| f64::from(step) * OPACITY_STEP)
+        .find(|&alpha| passes(alpha))
+        .map(round)
+}
+
+/// Lowest contrast of the text over `tint` at `alpha` across the backgrounds,
+/// with the background luminance where it occurs
+fn worst_case(text_luminance: f64, tint: Rgba, alpha: f64, backgrounds: &[f64]) -> (f64, f64) {
+    backgrounds
+        .iter()
+        .map(|&background| {
+            let surface = tint.over(alpha, Rgba::grey(background)).luminance();
+            (contrast_ratio(text_luminance, surface), background)
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .unwrap_or((21.0, 0.0))
+}
+
+/// Luminances to test: both edges and the middle of every occupied bin
+fn background_luminances(histogram: &[f64]) -> Vec<f64> {
+    let width = 1.0 / histogram.len() as f64;
+    histogram
+        .iter()
+        .enumerate()
+        .filter(|(_, &count)| count > 0.0)
+        .flat_map(|(i, _)| {
+            let low = i as f64 * width;
+            [low, low + width / 2.0, low + width]
+        })
+        .collect()
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOLD: &str = "#d1a054";
+    const GLASS: &str = "rgba(255, 255, 255, 0.05)";
+
+    fn rgba(text: &str) -> Rgba {
+        Rgba::parse(text).unwrap()
+    }
+
+    /// Histogram of `bins` even bins with only the listed ones occupied
+    fn histogram(bins: usize, occupied: &[usize]) -> Vec<f64> {
+        (0..bins).map(|i| if occupied.contains(&i) { 10.0 } else { 0.0 }).collect()
+    }
+
+    #[test]
+    fn parses_hex_and_functional_colours() {
+        assert_eq!(rgba("#fff"), Rgba::WHITE);
+        assert_eq!(rgba(" #D1A054 "), Rgba::opaque(209.0, 160.0, 84.0));
+        assert_eq!(rgba("#00000080"), Rgba { a: 128.0 / 255.0, ..Rgba::BLACK });
+        assert_eq!(rgba("rgb(209, 160, 84)"), rgba(GOLD));
+        assert_eq!(rgba(GLASS), Rgba { a: 0.05, ..Rgba::WHITE });
+        // Space-separated syntax, and alpha clamped into range
+        assert_eq!(rgba("rgb(0 0 0 / 0.5)"), Rgba { a: 0.5, ..Rgba::BLACK });
+        assert_eq!(rgba("rgba(0, 0, 0, 2)").a, 1.0);
+
+        for text in ["#ff", "#ggg", "#12345", "rgb(1, 2)", "rgba(1, 2, 3, 4, 5)", "rgb(1, 2, x)", "gold", ""] {
+            assert!(Rgba::parse(text).is_err(), "{text:?}");
+        }
+        assert_eq!(Rgba::parse("hsl(0, 0%, 0%)"), Err("Invalid colour \"hsl(0, 0%, 0%)\"".to_string()));
+    }
+
+    #[test]
+    fn luminance_and_ratios_match_wcag() {
+        assert_eq!(contrast_ratio(Rgba::WHITE.luminance(), Rgba::BLACK.luminance()), 21.0);
+        assert!((rgba(GOLD).luminance() - 0.3934).abs() < 1e-4);
+        assert!((Rgba::grey(0.3934).luminance() - 0.3934).abs() < 1e-9);
+        assert_eq!(rgba("#808080").over(0.5, Rgba::WHITE).hex(), "#c0c0c0");
+    }
+
+    #[test]
+    fn gold_on_glass_over_a_dark_photo() {
+        let report = check_contrast(rgba(GOLD), rgba(GLASS), &histogram(50, &[0]), false).unwrap();
+        assert!(report.aa.passes, "{report:?}");
+        assert_eq!(report.worst_background_luminance, 0.02);
+        assert!(report.worst_ratio >= AA_NORMAL && report.worst_ratio < AAA_NORMAL);
+        assert_eq!((report.aa.contrasting_tint.as_ref(), report.aa.text_shadow.as_ref()), (None, None));
+        // Headroom: even less tint would still pass
+        assert!(report.aa.card_opacity.is_some_and(|opacity| opacity <= 0.05));
+        assert!(!report.aaa.passes);
+    }
+
+    #[test]
+    fn gold_on_glass_over_a_bright_photo_suggests_a_dark_tint() {
+        let gold = rgba(GOLD);
+        let report = check_contrast(gold, rgba(GLASS), &[1.0; 10], false).unwrap();
+        assert_eq!(report.worst_ratio, 1.05);
+        assert!(!report.aa.passes && !report.aaa.passes);
+        // More white tint only makes it worse
+        assert_eq!(report.aa.card_opacity, None);
+
+        let tint = report.aa.contrasting_tint.unwrap();
+        assert_eq!((tint.color.as_str(), tint.opacity), ("#000000", 0.76));
+        assert_eq!(tint.css, "rgba(0, 0, 0, 0.76)");
+        let shadow = report.aa.text_shadow.unwrap();
+        assert_eq!(shadow.css, "0 0 4px rgba(0, 0, 0, 0.76)");
+
+        // The suggested tint passes, and a step less doesn't
+        let passing = check_contrast(gold, rgba(&tint.css), &[1.0; 10], false).unwrap();
+        assert!(passing.aa.passes);
+        let short = check_contrast(gold, rgba("rgba(0, 0, 0, 0.75)"), &[1.0; 10], false).unwrap();
+        assert!(!short.aa.passes);
+
+        // Large text needs less
+        let large = check_contrast(gold, rgba(GLASS), &[1.0; 10], true).unwrap();
+        assert_eq!(large.aa.target_ratio, AA_LARGE);
+        assert_eq!(large.aa.contrasting_tint.unwrap().opacity, 0.66);
+    }
+
+    #[test]
+    fn empty_histogram_is_an_error() {
+        assert!(check_contrast(rgba(GOLD), rgba(GLASS), &[0.0; 8], false).is_err());
+        assert!(check_contrast(rgba(GOLD), rgba(GLASS), &[], false).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::menu::MenuSection;
+
+/// Inclusive, 1-based range of OCR text lines something was read from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SourceLines {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+impl SourceLines {
+    /// Range covering a single 0-based line index
+    pub(crate) fn at(index: usize) -> SourceLines {
+        SourceLines {
+            start: index + 1,
+            end: index + 1,
+        }
+    }
+
+    pub(crate) fn extend_to(&mut self, index: usize) {
+        self.end = self.end.max(index + 1);
+    }
+}
+
+/// What a reviewer should check before the menu is published
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum WarningKind {
+    /// A token in price position that doesn't read as a price ("l2", "$1O")
+    UnparseablePrice,
+    /// An item with neither a price nor "market price"
+    MissingPrice,
+    /// Lower-case text with no item above it to belong to
+    OrphanDescription,
+    /// The same item name appears more than once
+    DuplicateItemName,
+    /// An item name ending in a bare number, usually a price or a second
+    /// price column that wasn't split off ("Pinot Noir 12")
+    TrailingNumber,
+    /// An item with no name, such as a price on a line of its own
+    EmptyName,
+}
+
+/// A problem found while extracting the menu, tied to its source lines
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ExtractionWarning {
+    pub(crate) kind: WarningKind,
+    pub(crate) message: String,
+    pub(crate) lines: SourceLines,
+}
+
+/// Scores every section and item and collects warnings for the review screen
+///
+/// Item confidence starts at 1.0 and loses weight for each sign of a misread:
+/// a missing or garbled price, a name that looks like description text, or
+/// a description long enough that neighbouring items were probably merged
+/// into it. A section scores the mean of its items, less when it has no
+/// heading.
+pub(crate) fn diagnose(sections: &mut [MenuSection], lines: &[&str]) -> Vec<ExtractionWarning> {
+    let mut warnings = Vec::new();
+    let mut seen: HashMap<String, SourceLines> = HashMap::new();
+
+    for section in sections.iter_mut() {
+        for item in &mut section.items {
+            let mut confidence: f64 = 1.0;
+
+            let garbled = (item.lines.start..=item.lines.end)
+                .filter_map(|number| lines.get(number - 1))
+                .find_map(|line| garbled_price(line));
+            if let Some(token) = garbled {
+                confidence -= 0.3;
+                warnings.push(ExtractionWarning {
+                    kind: WarningKind::UnparseablePrice,
+                    message: format!("\"{}\" in \"{}\" looks like a price but couldn't be read", token, item.name),
+                    lines: item.lines,
+                });
+            } else if item.prices.is_empty() && !item.market_price {
+                confidence -= 0.4;
+                warnings.push(ExtractionWarning {
+                    kind: WarningKind::MissingPrice,
+                    message: format!("No price found for \"{}\"", item.name),
+                    lines: item.lines,
+                });
+            }
+
+            if item.name.trim().is_empty() {
+                confidence -= 0.5;
+                warnings.push(ExtractionWarning {
+                    kind: WarningKind::EmptyName,
+                    message: format!("The item on line {} has no name", item.lines.start),
+                    lines: item.lines,
+                });
+            } else if let Some(number) = trailing_number(&item.name) {
+                confidence -= 0.2;
+                warnings.push(ExtractionWarning {
+                    kind: WarningKind::TrailingNumber,
+                    message: format!("\"{}\" ends in \"{}\", which may be a price", item.name, number),
+                    lines: item.lines,
+                });
+            }
+
+            if item.name.starts_with(|c: char| c.is_lowercase()) {
+                confidence -= 0.3;
+                warnings.push(ExtractionWarning {
+                    kind: WarningKind::OrphanDescription,
+                    message: format!("\"{}\" reads like a description with no item above it", item.name),
+                    lines: item.lines,
+                });
+            }
+
+            let key = item.name.to_lowercase();
+            if key.is_empty() {
+                // Nameless items are already reported, not duplicates
+            } else if let Some(first) = seen.get(&key) {
+                confidence -= 0.1;
+                warnings.push(ExtractionWarning {
+                    kind: WarningKind::DuplicateItemName,
+                    message: format!("\"{}\" also appears on line {}", item.name, first.start),
+                    lines: item.lines,
+                });
+            } else {
+                seen.insert(key, item.lines);
+            }
+
+            if item.name.split_whitespace().count() > 8 {
+                confidence -= 0.2;
+            }
+            if item.description.len() > 300 {
+                confidence -= 0.1;
+            }
+            confidence -= 0.3 * noise_ratio(&item.name);
+
+            item.confidence = round(confidence.clamp(0.0, 1.0));
+        }
+
+        let mean = section.items.iter().map(|i| i.confidence).sum::<f64>() / section.items.len().max(1) as f64;
+        let heading = if section.title.is_empty() { 0.8 } else { 1.0 };
+        section.confidence = round(mean * heading);
+    }
+
+    warnings
+}
+
+/// Returns the last token of a line when it sits where a price goes but fails
+/// to parse: OCR letter swaps ("l2", "1O"), stray dots ("12.5.0") or a "$"
+/// followed by junk
+pub(crate) fn garbled_price(line: &str) -> Option<&str> {
+    let token = line.split_whitespace().last()?;
+    let body = token.trim_start_matches('$');
+    let has_digit = body.chars().any(|c| c.is_ascii_digit());
+    let confusable = |c: char| matches!(c, 'l' | 'I' | 'O' | 'o' | 'S');
+    let price_shaped = !body.is_empty()
+        && has_digit
+        && body.len() <= 7
+        && body.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | ',') || confusable(c))
+        && (token.starts_with('$') || body.starts_with(|c: char| c.is_ascii_digit() || confusable(c)));
+    if !price_shaped {
+        return None;
+    }
+
+    let clean_number = body.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | ','));
+    let separators = body.chars().filter(|c| matches!(c, '.' | ',')).count();
+    let garbled = !clean_number || separators > 1;
+    garbled.then_some(token)
+}
+
+/// Returns the last word of an item name when it is a bare number; vintages
+/// ("Sancerre 2022") are part of the name and don't count
+fn trailing_number(name: &str) -> Option<&str> {
+    let mut words = name.split_whitespace();
+    let token = words.next_back()?;
+    words.next_back()?;
+    let number = token.trim_start_matches('$');
+    let bare = !number.is_empty() && number.chars().all(|c| c.is_ascii_digit() || c == '.');
+    let vintage = number.len() == 4 && number.parse::<u32>().is_ok_and(|year| (1900..=2099).contains(&year));
+    (bare && number.chars().any(|c| c.is_ascii_digit()) && !vintage).then_some(token)
+}
+
+/// Share of characters that are neither letters, digits nor ordinary
+/// punctuation, a sign of OCR noise
+fn noise_ratio(text: &str) -> f64 {
+    let total = text.chars().count();
+    if total == 0 {
+        return 0.0;
+    }
+    let noise = text
+        .chars()
+        .filter(|&c| !(c.is_alphanumeric() || c.is_whitespace() || "&'-,.()/\"’".contains(c)))
+        .count();
+    noise as f64 / total as f64
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::menu::extract_menu;
+
+    use super::*;
+
+    fn kinds(text: &str) -> Vec<WarningKind> {
+        extract_menu(text).warnings.iter().map(|w| w.kind).collect()
+    }
+
+    #[test]
+    fn clean_menu_scores_full_confidence() {
+        let extraction = extract_menu("STARTERS\nWings - buffalo or bbq 12\nFries 6");
+        assert!(extraction.warnings.is_empty(), "{:?}", extraction.warnings);
+        assert_eq!(extraction.sections[0].confidence, 1.0);
+        assert!(extraction.sections[0].items.iter().all(|item| item.confidence == 1.0));
+    }
+
+    #[test]
+    fn garbled_and_missing_prices() {
+        let extraction = extract_menu("MAINS\nBurger l2\n\nHouse salad with lemon");
+        let warnings = &extraction.warnings;
+        assert_eq!(warnings[0].kind, WarningKind::UnparseablePrice);
+        assert_eq!(warnings[0].lines, SourceLines { start: 2, end: 2 });
+        assert_eq!(warnings[1].kind, WarningKind::MissingPrice);
+        assert_eq!(warnings[1].lines.start, 4);
+        assert_eq!(extraction.sections[0].items[0].confidence, 0.7);
+
+        assert_eq!(garbled_price("Burger $1O"), Some("$1O"));
+        assert_eq!(garbled_price("Burger 12.5.0"), Some("12.5.0"));
+        assert_eq!(garbled_price("Burger 12.50"), None);
+        assert_eq!(garbled_price("Burger"), None);
+    }
+
+    #[test]
+    fn orphan_descriptions_and_duplicates() {
+        assert_eq!(kinds("served with fries 12"), [WarningKind::OrphanDescription]);
+        let extraction = extract_menu("Fries 6\n\nSIDES\nFries 7");
+        assert_eq!(extraction.warnings.len(), 1);
+        assert_eq!(extraction.warnings[0].kind, WarningKind::DuplicateItemName);
+        assert_eq!(extraction.warnings[0].message, "\"Fries\" also appears on line 1");
+    }
+
+    #[test]
+    fn names_ending_in_a_bare_number() {
+        assert_eq!(kinds("Pinot Noir 12 46 52"), [WarningKind::TrailingNumber]);
+        assert_eq!(trailing_number("Pinot Noir 12"), Some("12"));
+        assert_eq!(trailing_number("Bin 4.5"), Some("4.5"));
+        // Vintages and single words are left alone
+        assert_eq!(trailing_number("Sancerre 2022"), None);
+        assert_eq!(trailing_number("Sancerre"), None);
+        assert_eq!(trailing_number("Sancerre No. 5"), Some("5"));
+    }
+
+    #[test]
+    fn price_without_a_name() {
+        let extraction = extract_menu("DESSERTS\nTiramisu 9\n\n12");
+        assert_eq!(extraction.warnings.len(), 1, "{:?}", extraction.warnings);
+        let warning = &extraction.warnings[0];
+        assert_eq!(warning.kind, WarningKind::EmptyName);
+        assert_eq!(warning.lines.start, 4);
+        assert_eq!(extraction.sections[0].items[1].confidence, 0.5);
+    }
+}
@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+
+use crate::menu::MenuSection;
+use crate::normalize;
+use crate::price::split_trailing_prices;
+
+/// Footnote symbols menus use to point items at notes printed below them
+const FOOTNOTE_SYMBOLS: [char; 6] = ['*', '†', '‡', '§', '¶', '^'];
+
+/// Dietary tags a catalog keyword can name, in normalized form
+const KEYWORD_TAGS: [&str; 20] = [
+    "gluten_free",
+    "gluten_free_option",
+    "vegetarian",
+    "vegetarian_option",
+    "vegan",
+    "vegan_option",
+    "dairy_free",
+    "dairy_free_option",
+    "lactose_free",
+    "nut_free",
+    "contains_nuts",
+    "halal",
+    "kosher",
+    "low_calorie",
+    "low_fat",
+    "low_salt",
+    "low_sodium",
+    "diabetic",
+    "low_abv",
+    "non_alcoholic",
+];
+
+/// Markers the menu defines for itself in its legend and footnotes
+#[derive(Debug, Default)]
+pub(crate) struct DietaryLegend {
+    /// Upper-cased letter codes ("GF", "N") or symbols ("🌱", "**")
+    markers: HashMap<String, Vec<&'static str>>,
+}
+
+impl DietaryLegend {
+    /// Pulls legend entries ("GF = gluten free   V - vegetarian") and
+    /// footnotes ("*consuming raw or undercooked…") out of the menu text
+    ///
+    /// Recognised lines are blanked rather than removed so line numbers stay
+    /// aligned with the OCR output.
+    pub(crate) fn extract(lines: &mut [String]) -> DietaryLegend {
+        let mut legend = DietaryLegend::default();
+        for line in lines.iter_mut() {
+            // Priced lines are items, even "V - vegetarian lasagna 14"
+            if split_trailing_prices(line, false).1.is_some() {
+                continue;
+            }
+            let entries = parse_footnote(line).map(|entry| vec![entry]).or_else(|| parse_legend_line(line));
+            if let Some(entries) = entries {
+                for (marker, tags) in entries {
+                    legend.markers.entry(marker).or_default().extend(tags);
+                }
+                line.clear();
+            }
+        }
+        legend
+    }
+
+    fn lookup(&self, code: &str) -> Option<Vec<&'static str>> {
+        self.markers
+            .get(&code.to_uppercase())
+            .cloned()
+            .or_else(|| builtin_code(code).map(|tags| tags.to_vec()))
+    }
+
+    /// Symbol markers, longest first so "**" wins over "*"
+    fn symbols(&self) -> Vec<(&str, &[&'static str])> {
+        let mut symbols: Vec<(&str, &[&'static str])> = self
+            .markers
+            .iter()
+            .filter(|(marker, _)| !marker.chars().any(char::is_alphanumeric))
+            .map(|(marker, tags)| (marker.as_str(), tags.as_slice()))
+            .collect();
+        symbols.sort_by(|a, b| b.0.chars().count().cmp(&a.0.chars().count()).then(a.0.cmp(b.0)));
+        symbols
+    }
+}
+
+/// Moves marker groups printed after the price in front of it, so
+/// "Caesar 12 (GF)" and "Oysters 18*" still parse their prices
+pub(crate) fn hoist_trailing_markers(line: &str) -> String {
+    let mut body = line.trim_end();
+    let mut markers: Vec<&str> = Vec::new();
+    loop {
+        if body.ends_with(')') || body.ends_with(']') {
+            let open = if body.ends_with(')') { '(' } else { '[' };
+            match body.rfind(open) {
+                Some(start) if is_code_group(&body[start + 1..body.len() - 1]) => {
+                    markers.push(&body[start..]);
+                    body = body[..start].trim_end();
+                    continue;
+                }
+                _ => break,
+            }
+        }
+        let symbols = body.len() - body.trim_end_matches(FOOTNOTE_SYMBOLS).len();
+        if symbols > 0 && body[..body.len() - symbols].ends_with(|c: char| c.is_ascii_digit()) {
+            markers.push(&body[body.len() - symbols..]);
+            body = &body[..body.len() - symbols];
+            continue;
+        }
+        break;
+    }
+
+    if markers.is_empty() {
+        return line.to_string();
+    }
+
+    // Re-insert the markers just before the trailing price token
+    let (head, price) = match body.rsplit_once(char::is_whitespace) {
+        Some((head, price)) => (head, price),
+        None => return line.to_string(),
+    };
+    let markers: Vec<&str> = markers.into_iter().rev().collect();
+    format!("{} {} {}", head, markers.join(" "), price)
+}
+
+/// Resolves an item's markers into normalized tags and strips them from its
+/// name and description
+pub(crate) fn tag_sections(sections: &mut [MenuSection], legend: &DietaryLegend) {
+    for item in sections.iter_mut().flat_map(|s| s.items.iter_mut()) {
+        let mut tags = Vec::new();
+        item.name = strip_markers(&item.name, legend, &mut tags);
+        item.description = strip_markers(&item.description, legend, &mut tags);
+        tags.extend(phrase_tags(&item.description, false));
+
+        for tag in tags {
+            if !item.dietary_tags.iter().any(|t| t == tag) {
+                item.dietary_tags.push(tag.to_string());
+            }
+        }
+    }
+}
+
+/// Removes code groups, trailing codes and symbols, collecting their tags
+fn strip_markers(text: &str, legend: &DietaryLegend, tags: &mut Vec<&'static str>) -> String {
+    let mut text = text.to_string();
+
+    // Bracketed code groups anywhere: "(GF)", "(gf/v)", "[VG, N]"
+    for (open, close) in [('(', ')'), ('[', ']')] {
+        let mut search_from = 0;
+        while let Some(offset) = text[search_from..].find(open) {
+            let start = search_from + offset;
+            let Some(length) = text[start..].find(close) else {
+                break;
+            };
+            let end = start + length;
+            let inner = &text[start + 1..end];
+            let resolved: Option<Vec<Vec<&'static str>>> = split_codes(inner).map(|code| legend.lookup(code)).collect();
+            match resolved {
+                Some(resolved) if is_code_group(inner) => {
+                    tags.extend(resolved.into_iter().flatten());
+                    text.replace_range(start..=end, " ");
+                }
+                _ => search_from = end + 1,
+            }
+        }
+    }
+
+    // Symbols defined by the legend or footnotes
+    for (symbol, symbol_tags) in legend.symbols() {
+        if text.contains(symbol) {
+            tags.extend(symbol_tags.iter().copied());
+            text = text.replace(symbol, " ");
+        }
+    }
+
+    // Bare codes trailing the name: "Caesar Salad GF V", collected right to left
+    let mut words: Vec<&str> = text.split_whitespace().collect();
+    let mut trailing: Vec<(&str, Vec<&'static str>)> = Vec::new();
+    while words.len() > trailing.len() + 1 {
+        let word = words[words.len() - 1 - trailing.len()];
+        match legend.lookup(word) {
+            Some(code_tags) if word.chars().all(|c| c.is_ascii_uppercase()) => trailing.push((word, code_tags)),
+            _ => break,
+        }
+    }
+    // A lone letter only counts once it follows a longer code, so the "V" of
+    // "GF V" is a marker but the one in "Henry V" stays in the name
+    while trailing.last().is_some_and(|(code, _)| code.len() < 2) {
+        trailing.pop();
+    }
+    words.truncate(words.len() - trailing.len());
+    tags.extend(trailing.into_iter().rev().flat_map(|(_, code_tags)| code_tags));
+
+    // Footnote references the legend doesn't explain are still noise
+    words
+        .join(" ")
+        .trim_end_matches(FOOTNOTE_SYMBOLS)
+        .trim_start_matches(FOOTNOTE_SYMBOLS)
+        .trim()
+        .to_string()
+}
+
+/// Parses "*Consuming raw or undercooked…" into its symbol and tags
+fn parse_footnote(line: &str) -> Option<(String, Vec<&'static str>)> {
+    let line = line.trim();
+    let first = line.chars().next()?;
+    if !FOOTNOTE_SYMBOLS.contains(&first) {
+        return None;
+    }
+    let symbol: String = line.chars().take_while(|&c| c == first).collect();
+    let tags = phrase_tags(&line[symbol.len()..], true);
+    (!tags.is_empty()).then_some((symbol, tags))
+}
+
+/// Parses a legend line such as "GF = gluten free   V - vegetarian" or
+/// "(N) contains nuts  🌶 spicy"; every entry must resolve to a known tag
+fn parse_legend_line(line: &str) -> Option<Vec<(String, Vec<&'static str>)>> {
+    let spread = line.replace('=', " = ");
+    let words: Vec<&str> = spread.split_whitespace().collect();
+
+    let starts: Vec<usize> = (0..words.len()).filter(|&i| entry_code(&words, i).is_some()).collect();
+    if starts.first() != Some(&0) {
+        return None;
+    }
+
+    let mut entries = Vec::new();
+    for (n, &start) in starts.iter().enumerate() {
+        let (code, consumed) = entry_code(&words, start)?;
+        let end = starts.get(n + 1).copied().unwrap_or(words.len());
+        let meaning = words[start + consumed..end].join(" ");
+        let tags = phrase_tags(meaning.trim_end_matches([',', ';', '|']), true);
+        if tags.is_empty() {
+            return None;
+        }
+        entries.push((code, tags));
+    }
+    Some(entries)
+}
+
+/// Returns the marker starting a legend entry at `index` and how many words
+/// it spans together with its separator
+fn entry_code(words: &[&str], index: usize) -> Option<(String, usize)> {
+    let word = words[index];
+    let next = words.get(index + 1).copied();
+    let separated = |consumed: usize| match next {
+        Some("=" | "-" | "–" | "—" | ":") => Some(consumed + 1),
+        _ => None,
+    };
+
+    if let Some(inner) = word.strip_prefix('(').and_then(|w| w.strip_suffix(')')) {
+        let code = marker_code(inner)?;
+        return Some((code, separated(1).unwrap_or(1)));
+    }
+    if let Some(inner) = word.strip_suffix(':') {
+        return Some((marker_code(inner)?, 1));
+    }
+    let code = marker_code(word)?;
+    // Symbols read naturally without a separator: "🌶 spicy"
+    if !code.chars().any(char::is_alphanumeric) {
+        return Some((code, separated(1).unwrap_or(1)));
+    }
+    Some((code, separated(1)?))
+}
+
+/// A legend marker is a short upper-case code or a run of symbols
+fn marker_code(word: &str) -> Option<String> {
+    let letters = !word.is_empty() && word.len() <= 4 && word.chars().all(|c| c.is_ascii_uppercase());
+    let symbols = !word.is_empty()
+        && word.chars().count() <= 3
+        && word.chars().all(|c| !c.is_alphanumeric() && !"()[]=-–—:,;|.&/+".contains(c));
+    (letters || symbols).then(|| word.to_string())
+}
+
+fn split_codes(group: &str) -> impl Iterator<Item = &str> {
+    group.split([',', '/', '&', ' ']).filter(|code| !code.is_empty())
+}
+
+/// A bracket group made only of short letter codes, such as "GF, V"
+fn is_code_group(group: &str) -> bool {
+    let mut codes = split_codes(group).peekable();
+    codes.peek().is_some()
+        && codes.all(|code| code.len() <= 4 && code.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+/// Codes that mean the same thing on nearly every menu
+fn builtin_code(code: &str) -> Option<&'static [&'static str]> {
+    let tags: &'static [&'static str] = match code.to_uppercase().as_str() {
+        "GF" => &["gluten_free"],
+        "GFO" | "GFA" => &["gluten_free_option"],
+        "V" | "VEG" => &["vegetarian"],
+        "VO" => &["vegetarian_option"],
+        "VG" | "VE" | "VGN" => &["vegan"],
+        "VGO" => &["vegan_option"],
+        "DF" => &["dairy_free"],
+        "DFO" => &["dairy_free_option"],
+        "N" => &["contains_nuts"],
+        "NF" => &["nut_free"],
+        _ => return None,
+    };
+    Some(tags)
+}
+
+/// Normalizes a catalog keyword ("Gluten-Free", "low abv") into the
+/// dietary tag it names, if any
+pub(crate) fn keyword_tag(keyword: &str) -> Option<String> {
+    let tag = normalize::fold(keyword.trim()).replace(['-', ' '], "_");
+    KEYWORD_TAGS.contains(&tag.as_str()).then_some(tag)
+}
+
+/// Maps free text such as "contains nuts" or "gluten free upon request" to tags
+///
+/// In legends and footnotes a bare allergen ("(N) nuts") implies "contains";
+/// in item descriptions only the list after an explicit "contains" counts,
+/// up to the end of its clause.
+fn phrase_tags(text: &str, legend: bool) -> Vec<&'static str> {
+    let lower = text.to_lowercase().replace('-', " ");
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let has = |word: &str| words.contains(&word);
+    let has_any = |options: &[&str]| options.iter().any(|o| has(o));
+    let optional = has_any(&["option", "optional", "available", "request", "substitute", "modified", "can"]);
+
+    let mut tags = Vec::new();
+    if has("raw") && has_any(&["undercooked", "consuming", "uncooked"]) {
+        tags.push("raw_or_undercooked");
+        return tags;
+    }
+
+    if lower.contains("gluten free") || lower.contains("gluten friendly") {
+        tags.push(if optional { "gluten_free_option" } else { "gluten_free" });
+    }
+    if lower.contains("dairy free") {
+        tags.push(if optional { "dairy_free_option" } else { "dairy_free" });
+    }
+    if lower.contains("nut free") {
+        tags.push("nut_free");
+    }
+    if legend && has("vegan") {
+        tags.push(if optional { "vegan_option" } else { "vegan" });
+    }
+    if legend && has("vegetarian") {
+        tags.push(if optional { "vegetarian_option" } else { "vegetarian" });
+    }
+    if legend && has_any(&["spicy", "hot"]) {
+        tags.push("spicy");
+    }
+
+    let listed: Vec<&str> = if legend {
+        words.clone()
+    } else {
+        lower
+            .split(['.', ';', ':', '(', ')', '!', '?'])
+            .flat_map(|clause| {
+                clause
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|w| !w.is_empty())
+                    .skip_while(|&w| w != "contains")
+                    .take_while(|&w| !matches!(w, "no" | "without" | "but"))
+            })
+            .collect()
+    };
+    if !listed.is_empty() {
+        let free_of = |allergen: &str| lower.contains(&format!("{} free", allergen));
+        let allergens: [(&[&str], &str); 9] = [
+            (&["nut", "nuts", "tree"], "contains_nuts"),
+            (&["peanut", "peanuts"], "contains_peanuts"),
+            (&["shellfish", "crustacean", "crustaceans"], "contains_shellfish"),
+            (&["fish"], "contains_fish"),
+            (&["dairy", "milk"], "contains_dairy"),
+            (&["egg", "eggs"], "contains_eggs"),
+            (&["soy", "soya"], "contains_soy"),
+            (&["gluten", "wheat"], "contains_gluten"),
+            (&["sesame"], "contains_sesame"),
+        ];
+        for (words, tag) in allergens {
+            if words.iter().any(|w| listed.contains(w)) && !words.iter().any(|w| free_of(w)) {
+                tags.push(tag);
+            }
+        }
+    }
+
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::parse_menu_text;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn legend_lines_define_markers_and_are_blanked() {
+        let mut menu = lines("Burrata 16\nGF = gluten free   V - vegetarian   🌶 spicy\nWings 14");
+        let legend = DietaryLegend::extract(&mut menu);
+        assert_eq!(menu, ["Burrata 16", "", "Wings 14"]);
+        assert_eq!(legend.lookup("gf"), Some(vec!["gluten_free"]));
+        assert_eq!(legend.lookup("V"), Some(vec!["vegetarian"]));
+        assert_eq!(legend.symbols(), [("🌶", &["spicy"][..])]);
+        // Priced lines are items even when they read like a legend
+        let mut item = lines("V - vegetarian lasagna 14");
+        DietaryLegend::extract(&mut item);
+        assert_eq!(item, ["V - vegetarian lasagna 14"]);
+    }
+
+    #[test]
+    fn footnotes_attach_to_starred_items() {
+        let sections = parse_menu_text(
+            "RAW BAR\nOysters* 18\nShrimp Cocktail 16\n\n*Consuming raw or undercooked shellfish may increase your risk",
+        );
+        let items = &sections[0].items;
+        assert_eq!(items[0].name, "Oysters");
+        assert_eq!(items[0].dietary_tags, ["raw_or_undercooked"]);
+        assert!(items[1].dietary_tags.is_empty());
+    }
+
+    #[test]
+    fn trailing_markers_move_in_front_of_the_price() {
+        assert_eq!(hoist_trailing_markers("Caesar 12 (V, GF)"), "Caesar (V, GF) 12");
+        assert_eq!(hoist_trailing_markers("Oysters 18*"), "Oysters * 18");
+        assert_eq!(hoist_trailing_markers("Soup (of the day) 9"), "Soup (of the day) 9");
+
+        let sections = parse_menu_text("SALADS\nCaesar 12 (V, GF)\nWedge GF 11");
+        let items = &sections[0].items;
+        assert_eq!((items[0].name.as_str(), items[0].price), ("Caesar", 12.0));
+        assert_eq!(items[0].dietary_tags, ["vegetarian", "gluten_free"]);
+        assert_eq!(items[1].name, "Wedge");
+        assert_eq!(items[1].dietary_tags, ["gluten_free"]);
+    }
+
+    #[test]
+    fn single_letter_codes_follow_longer_ones() {
+        let sections = parse_menu_text("SALADS\nCaesar Salad GF V 12\nHenry V 14");
+        let items = &sections[0].items;
+        assert_eq!(items[0].name, "Caesar Salad");
+        assert_eq!(items[0].dietary_tags, ["gluten_free", "vegetarian"]);
+        assert_eq!(items[1].name, "Henry V");
+        assert!(items[1].dietary_tags.is_empty());
+    }
+
+    #[test]
+    fn only_the_contains_list_names_allergens() {
+        assert_eq!(phrase_tags("no dairy, contains nuts", false), ["contains_nuts"]);
+        let tags = phrase_tags("contains nuts and soy. served with milk bread", false);
+        assert_eq!(tags, ["contains_nuts", "contains_soy"]);
+        assert!(phrase_tags("sesame crust, brown butter", false).is_empty());
+        assert_eq!(phrase_tags("nuts, sesame", true), ["contains_nuts", "contains_sesame"]);
+    }
+
+    #[test]
+    fn sections_tag_each_item_from_the_shared_legend() {
+        let sections = parse_menu_text(
+            "STARTERS\nHummus (VG) 9\nsesame, contains nuts\nCalamari 14\n\nMAINS\nRisotto (N) 24\n\n(N) contains tree nuts",
+        );
+        assert_eq!(sections[0].items[0].dietary_tags, ["vegan", "contains_nuts"]);
+        assert!(sections[0].items[1].dietary_tags.is_empty());
+        assert_eq!(sections[1].items[0].dietary_tags, ["contains_nuts"]);
+        assert_eq!(keyword_tag("Gluten-Free").as_deref(), Some("gluten_free"));
+        assert_eq!(keyword_tag("smoky"), None);
+    }
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::dietary;
+use crate::normalize;
+use crate::typo;
+
+/// Structured filters applied on top of a text query
+///
+/// Each list matches if the entry has any of its values, except `dietary`,
+/// where an entry must carry every requested tag. Values compare
+/// case-insensitively.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchFilters {
+    /// Cocktail category, "wine", or a dish's course
+    pub categories: Vec<String>,
+    pub spirits: Vec<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    /// Normalized dietary tags, e.g. "vegan" or "gluten_free"
+    pub dietary: Vec<String>,
+    /// Drops cocktails listing any of these ingredients and dishes naming
+    /// them as allergens or in their description
+    pub exclude_ingredients: Vec<String>,
+}
+
+/// How many matching entries share one facet value
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Facet counts for a result set, most common value first
+///
+/// Each facet is counted with every filter applied except its own, so the
+/// spirit chips still offer rum while the guest has gin selected.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Facets {
+    pub categories: Vec<FacetCount>,
+    pub spirits: Vec<FacetCount>,
+    pub dietary: Vec<FacetCount>,
+}
+
+/// Filterable attributes of one indexed entry, lower-cased at index time
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FacetValues {
+    pub(crate) category: Option<String>,
+    pub(crate) spirit: Option<String>,
+    /// Every price the entry is offered at, e.g. glass and bottle
+    pub(crate) prices: Vec<f64>,
+    pub(crate) dietary: Vec<String>,
+    /// Words of each ingredient or allergen, for exclusions
+    pub(crate) contents: Vec<Vec<String>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Facet {
+    Category,
+    Spirit,
+    Dietary,
+}
+
+impl SearchFilters {
+    pub(crate) fn matches(&self, values: &FacetValues) -> bool {
+        self.matches_except(values, None)
+    }
+
+    fn matches_except(&self, values: &FacetValues, skip: Option<Facet>) -> bool {
+        let any_of = |wanted: &[String], value: &Option<String>| {
+            wanted.is_empty() || value.as_ref().is_some_and(|v| wanted.iter().any(|w| normalize::fold(w.trim()) == *v))
+        };
+        if skip != Some(Facet::Category) && !any_of(&self.categories, &values.category) {
+            return false;
+        }
+        if skip != Some(Facet::Spirit) && !any_of(&self.spirits, &values.spirit) {
+            return false;
+        }
+        if skip != Some(Facet::Dietary)
+            && !self
+                .dietary
+                .iter()
+                .all(|tag| dietary::keyword_tag(tag).is_some_and(|tag| values.dietary.contains(&tag)))
+        {
+            return false;
+        }
+
+        if self.min_price.is_some() || self.max_price.is_some() {
+            let in_range = |price: f64| {
+                self.min_price.is_none_or(|min| price >= min) && self.max_price.is_none_or(|max| price <= max)
+            };
+            if !values.prices.iter().any(|&price| in_range(price)) {
+                return false;
+            }
+        }
+
+        !self.exclude_ingredients.iter().any(|excluded| {
+            let excluded: Vec<String> = typo::words(excluded).collect();
+            !excluded.is_empty()
+                && values
+                    .contents
+                    .iter()
+                    .any(|words| words.windows(excluded.len()).any(|window| window == excluded.as_slice()))
+        })
+    }
+}
+
+/// Counts facet values across `candidates`, each facet ignoring its own filter
+pub(crate) fn count_facets<'a>(
+    filters: &SearchFilters,
+    candidates: impl IntoIterator<Item = &'a FacetValues>,
+) -> Facets {
+    let mut categories: HashMap<&str, usize> = HashMap::new();
+    let mut spirits: HashMap<&str, usize> = HashMap::new();
+    let mut dietary: HashMap<&str, usize> = HashMap::new();
+
+    for values in candidates {
+        if filters.matches_except(values, Some(Facet::Category)) {
+            if let Some(category) = &values.category {
+                *categories.entry(category).or_default() += 1;
+            }
+        }
+        if filters.matches_except(values, Some(Facet::Spirit)) {
+            if let Some(spirit) = &values.spirit {
+                *spirits.entry(spirit).or_default() += 1;
+            }
+        }
+        if filters.matches_except(values, Some(Facet::Dietary)) {
+            for tag in &values.dietary {
+                *dietary.entry(tag).or_default() += 1;
+            }
+        }
+    }
+
+    Facets {
+        categories: sorted(categories),
+        spirits: sorted(spirits),
+        dietary: sorted(dietary),
+    }
+}
+
+fn sorted(counts: HashMap<&str, usize>) -> Vec<FacetCount> {
+    let mut counts: Vec<FacetCount> = counts
+        .into_iter()
+        .map(|(value, count)| FacetCount {
+            value: value.to_string(),
+            count,
+        })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    counts
+}
@@ -0,0 +1,273 @@
+use serde::{Deserialize, Serialize};
+
+/// Frames kept for the rolling statistics, about four seconds at 60 Hz
+pub(crate) const DEFAULT_CAPACITY: usize = 240;
+
+/// A frame this long is visible as a stutter even on a fast display
+const LONG_FRAME_MS: f64 = 50.0;
+
+/// Gaps longer than this are a hidden tab or a paused loop, not a slow frame
+const MAX_FRAME_MS: f64 = 1000.0;
+
+/// Shortest refresh interval assumed when estimating the display rate (240 Hz)
+const MIN_REFRESH_MS: f64 = 1000.0 / 240.0;
+
+/// Rolling frame statistics at the moment they were taken
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct FrameSnapshot {
+    pub(crate) fps: f64,
+    /// Frames currently in the window
+    pub(crate) frame_count: usize,
+    pub(crate) mean_ms: f64,
+    pub(crate) p50_ms: f64,
+    pub(crate) p95_ms: f64,
+    pub(crate) p99_ms: f64,
+    pub(crate) max_ms: f64,
+    /// Frames longer than 50 ms in the window
+    pub(crate) long_frames: usize,
+    /// Display refreshes the page missed, estimated from frame durations
+    pub(crate) dropped_frames: usize,
+    /// Dropped frames over all frames the display could have shown
+    pub(crate) dropped_frame_ratio: f64,
+    /// Estimated display refresh interval
+    pub(crate) refresh_interval_ms: f64,
+}
+
+/// Ring buffer of frame durations fed from `requestAnimationFrame` timestamps
+#[derive(Clone, Debug)]
+pub(crate) struct FrameSampler {
+    durations: Vec<f64>,
+    capacity: usize,
+    /// Slot the next duration is written to once the buffer is full
+    next: usize,
+    /// Frames recorded since the last `take_recent`, newest last
+    pending: usize,
+    last_timestamp: Option<f64>,
+}
+
+impl FrameSampler {
+    pub(crate) fn new(capacity: usize) -> FrameSampler {
+        let capacity = capacity.max(1);
+        FrameSampler {
+            durations: Vec::with_capacity(capacity),
+            capacity,
+            next: 0,
+            pending: 0,
+            last_timestamp: None,
+        }
+    }
+
+    /// Records a frame that started at `timestamp` milliseconds
+    ///
+    /// The first timestamp only sets the baseline. Timestamps that go
+    /// backwards or arrive after a long pause restart the baseline instead of
+    /// being counted as frames.
+    pub(crate) fn record(&mut self, timestamp: f64) {
+        if let Some(last) = self.last_timestamp {
+            let duration = timestamp - last;
+            if duration > 0.0 && duration <= MAX_FRAME_MS {
+                self.push(duration);
+            }
+        }
+        self.last_timestamp = Some(timestamp);
+    }
+
+    /// Forgets the baseline so a paused animation loop can resume cleanly
+    pub(crate) fn pause(&mut self) {
+        self.last_timestamp = None;
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.durations.clear();
+        self.next = 0;
+        self.pending = 0;
+        self.last_timestamp = None;
+    }
+
+    /// Average frames per second over the window
+    pub(crate) fn fps(&self) -> Option<f64> {
+        let total: f64 = self.durations.iter().sum();
+        (total > 0.0).then(|| self.durations.len() as f64 * 1000.0 / total)
+    }
+
+    pub(crate) fn snapshot(&self) -> FrameSnapshot {
+        statistics(self.durations.clone())
+    }
+
+    /// Statistics over only the frames recorded since the last call, so
+    /// successive judgements never see the same frame twice
+    ///
+    /// Returns `None` and keeps the frames pending until at least
+    /// `min_frames` have arrived.
+    pub(crate) fn take_recent(&mut self, min_frames: usize) -> Option<FrameSnapshot> {
+        if self.pending == 0 || self.pending < min_frames {
+            return None;
+        }
+        // Oldest first: the tail of the buffer, then the wrapped head
+        let (head, tail) = self.durations.split_at(self.next);
+        let skip = self.durations.len() - self.pending;
+        let recent: Vec<f64> = tail.iter().chain(head).skip(skip).copied().collect();
+        self.pending = 0;
+        Some(statistics(recent))
+    }
+
+    fn push(&mut self, duration: f64) {
+        if self.durations.len() < self.capacity {
+            self.durations.push(duration);
+        } else {
+            self.durations[self.next] = duration;
+            self.next = (self.next + 1) % self.capacity;
+        }
+        self.pending = (self.pending + 1).min(self.capacity);
+    }
+}
+
+/// Summarises frame durations, in any order
+fn statistics(mut sorted: Vec<f64>) -> FrameSnapshot {
+    if sorted.is_empty() {
+        return FrameSnapshot::default();
+    }
+
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let count = sorted.len();
+    let total: f64 = sorted.iter().sum();
+
+    // Most frames on a healthy page land on the display's refresh
+    // interval, so a low percentile estimates it without knowing the rate
+    let refresh = percentile(&sorted, 10.0).max(MIN_REFRESH_MS);
+    let dropped: usize = sorted
+        .iter()
+        .map(|d| ((d / refresh).round() as usize).saturating_sub(1))
+        .sum();
+
+    FrameSnapshot {
+        fps: round(count as f64 * 1000.0 / total),
+        frame_count: count,
+        mean_ms: round(total / count as f64),
+        p50_ms: round(percentile(&sorted, 50.0)),
+        p95_ms: round(percentile(&sorted, 95.0)),
+        p99_ms: round(percentile(&sorted, 99.0)),
+        max_ms: round(sorted[count - 1]),
+        long_frames: sorted.iter().filter(|&&d| d > LONG_FRAME_MS).count(),
+        dropped_frames: dropped,
+        dropped_frame_ratio: round(dropped as f64 / (dropped + count) as f64),
+        refresh_interval_ms: round(refresh),
+    }
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A sampler fed frames of the given durations, starting at 0 ms
+    fn sampler(capacity: usize, durations: &[f64]) -> FrameSampler {
+        let mut sampler = FrameSampler::new(capacity);
+        let mut timestamp = 0.0;
+        sampler.record(timestamp);
+        for duration in durations {
+            timestamp += duration;
+            sampler.record(timestamp);
+        }
+        sampler
+    }
+
+    #[test]
+    fn ring_buffer_keeps_the_latest_frames() {
+        let mut frames = sampler(3, &[10.0, 20.0, 30.0, 40.0]);
+        let snapshot = frames.snapshot();
+        assert_eq!(snapshot.frame_count, 3);
+        assert_eq!((snapshot.mean_ms, snapshot.max_ms), (30.0, 40.0));
+
+        // Wraps past the end of the buffer back to the oldest slot
+        frames.record(150.0);
+        frames.record(210.0);
+        frames.record(280.0);
+        let snapshot = frames.snapshot();
+        assert_eq!(snapshot.frame_count, 3);
+        assert_eq!(snapshot.mean_ms, 60.0);
+        assert_eq!(frames.fps(), Some(1000.0 / 60.0));
+    }
+
+    #[test]
+    fn gaps_pauses_and_backward_steps_only_reset_the_baseline() {
+        let mut frames = sampler(DEFAULT_CAPACITY, &[]);
+        assert_eq!(frames.snapshot(), FrameSnapshot::default());
+        assert_eq!(frames.fps(), None);
+
+        frames.record(16.0);
+        frames.record(5000.0);
+        frames.record(4990.0);
+        frames.pause();
+        frames.record(9000.0);
+        frames.record(9020.0);
+        assert_eq!(frames.snapshot().frame_count, 2);
+
+        frames.clear();
+        frames.record(9040.0);
+        assert_eq!(frames.snapshot().frame_count, 0);
+    }
+
+    #[test]
+    fn recent_frames_are_only_taken_once() {
+        let mut frames = sampler(4, &[10.0, 20.0]);
+        assert_eq!(frames.take_recent(3), None);
+        frames.record(60.0);
+        assert_eq!(frames.take_recent(3).map(|s| s.max_ms), Some(30.0));
+        assert_eq!(frames.take_recent(1), None);
+
+        // Wrapped frames come out of the ring in order, and the full window
+        // is still there for `snapshot`
+        for timestamp in [100.0, 150.0, 210.0] {
+            frames.record(timestamp);
+        }
+        let recent = frames.take_recent(1).unwrap();
+        assert_eq!((recent.frame_count, recent.mean_ms), (3, 50.0));
+        assert_eq!(frames.snapshot().frame_count, 4);
+    }
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let durations: Vec<f64> = (1..=100).map(f64::from).collect();
+        let snapshot = sampler(DEFAULT_CAPACITY, &durations).snapshot();
+        assert_eq!(snapshot.frame_count, 100);
+        assert_eq!(snapshot.p50_ms, 50.0);
+        assert_eq!(snapshot.p95_ms, 95.0);
+        assert_eq!(snapshot.p99_ms, 99.0);
+        assert_eq!(snapshot.max_ms, 100.0);
+        assert_eq!(snapshot.mean_ms, 50.5);
+
+        assert_eq!(percentile(&[7.0], 99.0), 7.0);
+        assert_eq!(percentile(&[1.0, 2.0, 3.0, 4.0], 0.0), 1.0);
+    }
+
+    #[test]
+    fn long_frames_are_over_fifty_ms() {
+        let snapshot = sampler(DEFAULT_CAPACITY, &[16.0, 50.0, 50.5, 120.0, 16.0]).snapshot();
+        assert_eq!(snapshot.long_frames, 2);
+    }
+
+    #[test]
+    fn dropped_frames_count_missed_refreshes() {
+        let mut durations = vec![16.0; 8];
+        durations.extend([48.0, 48.0]);
+        let snapshot = sampler(DEFAULT_CAPACITY, &durations).snapshot();
+        assert_eq!(snapshot.refresh_interval_ms, 16.0);
+        assert_eq!(snapshot.dropped_frames, 4);
+        assert_eq!(snapshot.dropped_frame_ratio, 0.29);
+
+        // Steady frames drop nothing, and the estimate never goes under 240 Hz
+        let snapshot = sampler(DEFAULT_CAPACITY, &[1.0; 20]).snapshot();
+        assert_eq!(snapshot.refresh_interval_ms, 4.17);
+        assert_eq!((snapshot.dropped_frames, snapshot.dropped_frame_ratio), (0, 0.0));
+    }
+}
@@ -0,0 +1,257 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+
+use crate::ranking::{self, Field, RankingConfig};
+use crate::search::SearchDomain;
+use crate::typo;
+
+/// Where an indexed document lives in the catalog
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct DocRef {
+    pub(crate) domain: SearchDomain,
+    pub(crate) position: usize,
+}
+
+/// Occurrences of one term in one field of one document
+#[derive(Clone, Debug)]
+struct Posting {
+    document: u32,
+    field: Field,
+    /// Token offsets within the field; repeated fields such as ingredients
+    /// continue from the previous one after a gap, so phrases can't span two
+    positions: Vec<u32>,
+}
+
+/// Every posting for one term, ordered by document
+#[derive(Clone, Debug, Default)]
+struct PostingList {
+    document_frequency: u32,
+    postings: Vec<Posting>,
+}
+
+impl PostingList {
+    fn posting(&self, document: u32, field: Field) -> Option<&Posting> {
+        self.postings
+            .binary_search_by(|posting| (posting.document, posting.field).cmp(&(document, field)))
+            .ok()
+            .map(|i| &self.postings[i])
+    }
+}
+
+/// Normalized token postings for the whole catalog, built once at index time
+#[derive(Clone, Debug, Default)]
+pub(crate) struct InvertedIndex {
+    documents: Vec<DocRef>,
+    /// Token count of each non-empty field, per document
+    field_lengths: Vec<Vec<(Field, u32)>>,
+    average_lengths: HashMap<Field, f64>,
+    terms: HashMap<String, PostingList>,
+}
+
+impl InvertedIndex {
+    /// Tokenizes every document's fields; document ids follow iteration order
+    pub(crate) fn build(documents: impl IntoIterator<Item = (DocRef, Vec<(Field, String)>)>) -> InvertedIndex {
+        let mut index = InvertedIndex::default();
+        for (document, fields) in documents {
+            index.add(document, fields);
+        }
+
+        let mut totals: HashMap<Field, (u32, u32)> = HashMap::new();
+        for &(field, length) in index.field_lengths.iter().flatten() {
+            let total = totals.entry(field).or_default();
+            total.0 += length;
+            total.1 += 1;
+        }
+        index.average_lengths = totals
+            .into_iter()
+            .map(|(field, (length, count))| (field, f64::from(length) / f64::from(count)))
+            .collect();
+        index
+    }
+
+    fn add(&mut self, document: DocRef, fields: Vec<(Field, String)>) {
+        let id = self.documents.len() as u32;
+        self.documents.push(document);
+
+        // Per field: token count, and the position the next token takes
+        let mut lengths: Vec<(Field, u32, u32)> = Vec::new();
+        let mut occurrences: BTreeMap<(String, Field), Vec<u32>> = BTreeMap::new();
+        for (field, text) in fields {
+            let (length, next) = match lengths.iter().position(|&(f, _, _)| f == field) {
+                Some(i) => {
+                    let (_, length, next) = &mut lengths[i];
+                    *next += 1;
+                    (length, next)
+                }
+                None => {
+                    lengths.push((field, 0, 0));
+                    let (_, length, next) = lengths.last_mut().unwrap();
+                    (length, next)
+                }
+            };
+            for word in typo::words(&text) {
+                occurrences.entry((word, field)).or_default().push(*next);
+                *length += 1;
+                *next += 1;
+            }
+        }
+        let lengths: Vec<(Field, u32)> = lengths
+            .into_iter()
+            .filter(|&(_, length, _)| length > 0)
+            .map(|(field, length, _)| (field, length))
+            .collect();
+        self.field_lengths.push(lengths);
+
+        for ((term, field), positions) in occurrences {
+            let list = self.terms.entry(term).or_default();
+            if list.postings.last().is_none_or(|posting| posting.document != id) {
+                list.document_frequency += 1;
+            }
+            list.postings.push(Posting {
+                document: id,
+                field,
+                positions,
+            });
+        }
+    }
+
+    pub(crate) fn document(&self, id: u32) -> DocRef {
+        self.documents[id as usize]
+    }
+
+    /// Every distinct indexed term
+    pub(crate) fn terms(&self) -> impl Iterator<Item = &str> {
+        self.terms.keys().map(String::as_str)
+    }
+
+    /// Number of documents containing `term`
+    pub(crate) fn document_frequency(&self, term: &str) -> u32 {
+        self.terms.get(term).map_or(0, |list| list.document_frequency)
+    }
+
+    /// Number of indexed documents
+    pub(crate) fn document_count(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// BM25F score of `term` in every document containing it, only counting
+    /// `field` when one is given
+    pub(crate) fn score_term(&self, term: &str, field: Option<Field>, config: &RankingConfig) -> Vec<(u32, f64)> {
+        let Some(list) = self.terms.get(term) else {
+            return Vec::new();
+        };
+        let idf = ranking::idf(self.documents.len(), list.document_frequency);
+
+        list.postings
+            .chunk_by(|a, b| a.document == b.document)
+            .filter_map(|postings| {
+                let document = postings[0].document;
+                let frequency: f64 = postings
+                    .iter()
+                    .filter(|posting| field.is_none_or(|field| posting.field == field))
+                    .map(|posting| {
+                        config.field_frequency(
+                            posting.field,
+                            posting.positions.len(),
+                            self.field_length(document, posting.field),
+                            self.average_lengths.get(&posting.field).copied().unwrap_or(1.0),
+                        )
+                    })
+                    .sum();
+                (frequency > 0.0).then(|| (document, config.term_score(frequency, idf)))
+            })
+            .collect()
+    }
+
+    /// Documents where `words` appear next to each other, in order, within
+    /// one field, or within `field` when one is given
+    pub(crate) fn phrase_documents(&self, words: &[String], field: Option<Field>) -> HashSet<u32> {
+        let Some(lists) = words.iter().map(|word| self.terms.get(word)).collect::<Option<Vec<_>>>() else {
+            return HashSet::new();
+        };
+        let Some((first, rest)) = lists.split_first() else {
+            return HashSet::new();
+        };
+
+        first
+            .postings
+            .iter()
+            .filter(|posting| field.is_none_or(|field| posting.field == field))
+            .filter(|posting| {
+                posting.positions.iter().any(|&start| {
+                    rest.iter().zip(1..).all(|(list, offset)| {
+                        list.posting(posting.document, posting.field)
+                            .is_some_and(|next| next.positions.binary_search(&(start + offset)).is_ok())
+                    })
+                })
+            })
+            .map(|posting| posting.document)
+            .collect()
+    }
+
+    fn field_length(&self, document: u32, field: Field) -> u32 {
+        self.field_lengths[document as usize]
+            .iter()
+            .find(|&&(f, _)| f == field)
+            .map_or(0, |&(_, length)| length)
+    }
+}
+
+/// A document and its score, ordered worst to best; ties go to the document
+/// indexed first
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Scored {
+    score: f64,
+    document: u32,
+}
+
+impl Eq for Scored {}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.document.cmp(&self.document))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Keeps the `limit` best documents pushed so far in a min-heap, so ranking
+/// costs O(n log k) instead of sorting every match
+pub(crate) struct TopK {
+    limit: usize,
+    heap: BinaryHeap<Reverse<Scored>>,
+}
+
+impl TopK {
+    pub(crate) fn new(limit: usize) -> TopK {
+        TopK {
+            limit,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, document: u32, score: f64) {
+        let candidate = Scored { score, document };
+        if self.heap.len() < self.limit {
+            self.heap.push(Reverse(candidate));
+        } else if self.heap.peek().is_some_and(|Reverse(worst)| candidate > *worst) {
+            self.heap.pop();
+            self.heap.push(Reverse(candidate));
+        }
+    }
+
+    /// Documents with their scores, best first
+    pub(crate) fn into_sorted_vec(self) -> Vec<(u32, f64)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(scored)| (scored.document, scored.score))
+            .collect()
+    }
+}
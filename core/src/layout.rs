@@ -0,0 +1,572 @@
+use serde::Deserialize;
+
+use crate::price::parse_price;
+
+/// Pixel rectangle of a recognised word, origin at the top left
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub(crate) struct BoundingBox {
+    pub(crate) x0: f64,
+    pub(crate) y0: f64,
+    pub(crate) x1: f64,
+    pub(crate) y1: f64,
+}
+
+impl BoundingBox {
+    /// Orders the corners so `x0 <= x1` and `y0 <= y1`, whichever way round
+    /// the OCR engine reported them
+    fn normalized(self) -> BoundingBox {
+        BoundingBox {
+            x0: self.x0.min(self.x1),
+            y0: self.y0.min(self.y1),
+            x1: self.x0.max(self.x1),
+            y1: self.y0.max(self.y1),
+        }
+    }
+
+    fn height(&self) -> f64 {
+        self.y1 - self.y0
+    }
+
+    fn center_y(&self) -> f64 {
+        (self.y0 + self.y1) / 2.0
+    }
+}
+
+/// A word with its position, as produced by Tesseract or a cloud OCR API
+#[derive(Clone, Debug, Deserialize)]
+#[serde(from = "WordInput")]
+pub(crate) struct OcrWord {
+    pub(crate) text: String,
+    pub(crate) bbox: BoundingBox,
+    pub(crate) page: usize,
+}
+
+/// Accepts both `{ text, bbox: { x0, y0, x1, y1 } }` (Tesseract.js) and the
+/// flat `{ text, x0, y0, x1, y1 }`
+#[derive(Deserialize)]
+struct WordInput {
+    text: String,
+    #[serde(default)]
+    page: usize,
+    #[serde(flatten)]
+    bounds: BoundsInput,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BoundsInput {
+    Nested { bbox: BoundingBox },
+    Flat(BoundingBox),
+}
+
+impl From<WordInput> for OcrWord {
+    fn from(input: WordInput) -> OcrWord {
+        let bbox = match input.bounds {
+            BoundsInput::Nested { bbox } | BoundsInput::Flat(bbox) => bbox.normalized(),
+        };
+        OcrWord {
+            text: input.text,
+            bbox,
+            page: input.page,
+        }
+    }
+}
+
+/// Words sharing a baseline within one column
+struct Line {
+    words: Vec<OcrWord>,
+    top: f64,
+    bottom: f64,
+}
+
+impl Line {
+    fn text(&self) -> String {
+        self.words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Reads word boxes out of hOCR (`ocrx_word` spans with `bbox` titles)
+pub(crate) fn parse_hocr(hocr: &str) -> Vec<OcrWord> {
+    let mut words = Vec::new();
+    let mut page = 0;
+    let mut pages_seen = 0;
+    let mut rest = hocr;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[..=tag_end];
+        rest = &rest[tag_end + 1..];
+
+        if tag.contains("ocr_page") {
+            page = pages_seen;
+            pages_seen += 1;
+            continue;
+        }
+        if !tag.contains("ocrx_word") {
+            continue;
+        }
+        let Some(bbox) = attribute(tag, "title").and_then(|title| parse_bbox(&title)) else {
+            continue;
+        };
+
+        // The word's text is everything up to its closing span, minus any
+        // formatting tags such as <strong> nested inside it
+        let name = tag[1..].split(|c: char| c.is_whitespace() || c == '>').next().unwrap_or("span");
+        let close = format!("</{}", name);
+        let end = rest.find(&close).unwrap_or(rest.len());
+        let text = decode_entities(&strip_tags(&rest[..end]));
+        rest = &rest[end..];
+
+        let text = text.trim();
+        if !text.is_empty() {
+            words.push(OcrWord {
+                text: text.to_string(),
+                bbox,
+                page,
+            });
+        }
+    }
+
+    words
+}
+
+/// Rebuilds reading-order menu text from positioned words
+///
+/// Columns are found from vertical gutters that stay empty down the page,
+/// leader dots are dropped, and a right-aligned price lands on the line whose
+/// baseline it shares. Lines that cross a gutter (centred titles) are kept
+/// whole and split the page into bands read top to bottom. Vertical gaps
+/// become blank lines so the text parser can still see section spacing.
+pub(crate) fn layout_text(words: &[OcrWord]) -> String {
+    let mut pages: Vec<usize> = words.iter().map(|w| w.page).collect();
+    pages.sort_unstable();
+    pages.dedup();
+
+    pages
+        .into_iter()
+        .map(|page| {
+            let page_words: Vec<OcrWord> = words
+                .iter()
+                .filter(|w| w.page == page)
+                .filter_map(strip_leaders)
+                .collect();
+            page_text(page_words)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn page_text(words: Vec<OcrWord>) -> String {
+    if words.is_empty() {
+        return String::new();
+    }
+
+    let line_height = median(words.iter().map(|w| w.bbox.height()).collect()).max(1.0);
+    let gutters = find_gutters(&words, line_height);
+
+    // Lines across the whole page first, to find titles spanning a gutter
+    let full_lines = group_lines(words, line_height);
+    let mut output: Vec<String> = Vec::new();
+    let mut band: Vec<OcrWord> = Vec::new();
+
+    for line in full_lines {
+        let spans_gutter = line.words.iter().any(|w| {
+            gutters
+                .iter()
+                .any(|&(left, right)| w.bbox.x0 < right && w.bbox.x1 > left)
+        });
+        if spans_gutter {
+            output.push(band_text(std::mem::take(&mut band), &gutters, line_height));
+            output.push(line.text());
+        } else {
+            band.extend(line.words);
+        }
+    }
+    output.push(band_text(band, &gutters, line_height));
+
+    output
+        .into_iter()
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Reads a band of the page column by column
+fn band_text(words: Vec<OcrWord>, gutters: &[(f64, f64)], line_height: f64) -> String {
+    let mut columns: Vec<Vec<OcrWord>> = vec![Vec::new(); gutters.len() + 1];
+    for word in words {
+        let center = (word.bbox.x0 + word.bbox.x1) / 2.0;
+        let column = gutters.iter().filter(|&&(left, _)| center > left).count();
+        columns[column].push(word);
+    }
+
+    columns
+        .into_iter()
+        .filter(|column| !column.is_empty())
+        .map(|column| column_text(group_lines(column, line_height), line_height))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn column_text(lines: Vec<Line>, line_height: f64) -> String {
+    let mut text = String::new();
+    let mut previous_bottom: Option<f64> = None;
+    for line in lines {
+        if let Some(bottom) = previous_bottom {
+            let gap = line.top - bottom;
+            text.push('\n');
+            if gap > line_height * 1.2 {
+                text.push('\n');
+            }
+        }
+        text.push_str(&line.text());
+        previous_bottom = Some(line.bottom);
+    }
+    text
+}
+
+/// Clusters words into lines by baseline, then orders each line left to right
+fn group_lines(mut words: Vec<OcrWord>, line_height: f64) -> Vec<Line> {
+    words.sort_by(|a, b| a.bbox.center_y().total_cmp(&b.bbox.center_y()));
+
+    let tolerance = line_height * 0.5;
+    let mut lines: Vec<Line> = Vec::new();
+    for word in words {
+        // Baselines are compared rather than tops so a small price set in a
+        // different size still lines up with its item
+        let joins = lines.last().is_some_and(|line| {
+            let baseline = line.words.iter().map(|w| w.bbox.y1).fold(f64::MIN, f64::max);
+            (word.bbox.y1 - baseline).abs() <= tolerance
+                || (word.bbox.center_y() > line.top && word.bbox.center_y() < line.bottom)
+        });
+        match lines.last_mut() {
+            Some(line) if joins => {
+                line.top = line.top.min(word.bbox.y0);
+                line.bottom = line.bottom.max(word.bbox.y1);
+                line.words.push(word);
+            }
+            _ => lines.push(Line {
+                top: word.bbox.y0,
+                bottom: word.bbox.y1,
+                words: vec![word],
+            }),
+        }
+    }
+
+    for line in &mut lines {
+        line.words.sort_by(|a, b| a.bbox.x0.total_cmp(&b.bbox.x0));
+    }
+    lines
+}
+
+/// Finds vertical white bands that separate text columns
+///
+/// A band qualifies when it is wider than a couple of line heights, almost
+/// no lines cross it, and real text sits on both sides. A "column" that is
+/// nothing but prices is not a column: its prices belong to the items on
+/// their left, so that gutter is dropped.
+fn find_gutters(words: &[OcrWord], line_height: f64) -> Vec<(f64, f64)> {
+    let left_edge = words.iter().map(|w| w.bbox.x0).fold(f64::MAX, f64::min);
+    let right_edge = words.iter().map(|w| w.bbox.x1).fold(f64::MIN, f64::max);
+    let width = right_edge - left_edge;
+    if width <= 0.0 {
+        return Vec::new();
+    }
+
+    let bucket = (width / 1000.0).max(1.0);
+    let buckets = (width / bucket).ceil() as usize + 1;
+    let lines = group_lines(words.to_vec(), line_height);
+
+    let mut coverage = vec![0usize; buckets];
+    for line in &lines {
+        let mut covered = vec![false; buckets];
+        for word in &line.words {
+            let from = ((word.bbox.x0 - left_edge) / bucket) as usize;
+            let to = (((word.bbox.x1 - left_edge) / bucket) as usize).min(buckets - 1);
+            covered[from..=to].iter_mut().for_each(|c| *c = true);
+        }
+        for (count, covered) in coverage.iter_mut().zip(covered) {
+            *count += covered as usize;
+        }
+    }
+
+    // Centred titles may cross a gutter, so allow a few lines through
+    let allowed = (lines.len() / 10).max(1);
+    let min_width = line_height * 2.0;
+    let mut candidates = Vec::new();
+    let mut run_start: Option<usize> = None;
+    for (i, &count) in coverage.iter().enumerate() {
+        if count <= allowed && i > 0 && i < buckets - 1 {
+            run_start.get_or_insert(i);
+        } else if let Some(start) = run_start.take() {
+            let (left, right) = (left_edge + start as f64 * bucket, left_edge + i as f64 * bucket);
+            if right - left >= min_width {
+                candidates.push((left, right));
+            }
+        }
+    }
+
+    let mut gutters: Vec<(f64, f64)> = Vec::new();
+    let mut column_start = f64::MIN;
+    for (n, &(left, right)) in candidates.iter().enumerate() {
+        let column_end = candidates.get(n + 1).map_or(f64::MAX, |c| c.0);
+        let before: Vec<&OcrWord> = words
+            .iter()
+            .filter(|w| w.bbox.x1 <= left && w.bbox.x0 >= column_start)
+            .collect();
+        let after: Vec<&OcrWord> = words
+            .iter()
+            .filter(|w| w.bbox.x0 >= right && w.bbox.x1 <= column_end)
+            .collect();
+        let mostly_prices = |column: &[&OcrWord]| {
+            let prices = column.iter().filter(|w| parse_price(&w.text).is_some()).count();
+            prices * 10 >= column.len() * 8
+        };
+        if before.len() < 3 || after.len() < 3 || mostly_prices(&after) || mostly_prices(&before) {
+            continue;
+        }
+        gutters.push((left, right));
+        column_start = right;
+    }
+    gutters
+}
+
+/// Drops pure leader words ("........") and trims leaders glued to a word
+fn strip_leaders(word: &OcrWord) -> Option<OcrWord> {
+    let is_leader = |c: char| matches!(c, '.' | '…' | '·' | '_');
+    let mut text = word.text.as_str();
+    if text.ends_with("..") || text.ends_with('…') || text.ends_with("__") {
+        text = text.trim_end_matches(is_leader);
+    }
+    if text.starts_with("..") || text.starts_with('…') || text.starts_with("__") {
+        text = text.trim_start_matches(is_leader);
+    }
+    if text.is_empty() {
+        return None;
+    }
+    Some(OcrWord {
+        text: text.to_string(),
+        ..word.clone()
+    })
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f64::total_cmp);
+    values[values.len() / 2]
+}
+
+/// Extracts a quoted attribute value from a start tag
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let start = tag.find(&format!("{}=", name))? + name.len() + 1;
+    let quote = tag[start..].chars().next()?;
+    if quote != '"' && quote != '\'' {
+        return None;
+    }
+    let value = &tag[start + 1..];
+    let end = value.find(quote)?;
+    Some(value[..end].to_string())
+}
+
+/// Parses "bbox 10 20 60 34; x_wconf 95" from an hOCR title
+fn parse_bbox(title: &str) -> Option<BoundingBox> {
+    let bbox = title
+        .split(';')
+        .map(str::trim)
+        .find_map(|property| property.strip_prefix("bbox "))?;
+    let values: Vec<f64> = bbox.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+    match values.as_slice() {
+        &[x0, y0, x1, y1] => Some(BoundingBox { x0, y0, x1, y1 }.normalized()),
+        _ => None,
+    }
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';').filter(|&end| end <= 10) else {
+            decoded.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..end];
+        let character = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match character {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(text: &str, x0: f64, y0: f64, x1: f64, y1: f64) -> OcrWord {
+        OcrWord {
+            text: text.to_string(),
+            bbox: BoundingBox { x0, y0, x1, y1 },
+            page: 0,
+        }
+    }
+
+    /// A row of words starting at `x`, each 10 px per character plus a space
+    fn row(words: &mut Vec<OcrWord>, mut x: f64, y: f64, texts: &[&str]) {
+        for text in texts {
+            let width = text.chars().count() as f64 * 10.0;
+            words.push(word(text, x, y, x + width, y + 20.0));
+            x += width + 10.0;
+        }
+    }
+
+    /// Two columns, each with a heading and items whose prices sit at the
+    /// column's right edge behind leader dots
+    const TWO_COLUMNS: &str = r#"<html><body><div class='ocr_page' title='bbox 0 0 1000 400'>
+        <span class='ocrx_word' title='bbox 40 40 140 60; x_wconf 96'>STARTERS</span>
+        <span class='ocrx_word' title='bbox 600 40 650 60; x_wconf 96'>MAINS</span>
+        <span class='ocrx_word' title='bbox 40 80 110 100'>Oysters</span>
+        <span class='ocrx_word' title='bbox 120 80 340 100'>..............</span>
+        <span class='ocrx_word' title='bbox 370 84 390 100'>18</span>
+        <span class='ocrx_word' title='bbox 600 80 660 100'><strong>Burger</strong></span>
+        <span class='ocrx_word' title='bbox 930 82 950 100'>19</span>
+        <span class='ocrx_word' title='bbox 40 110 110 130'>Burrata</span>
+        <span class='ocrx_word' title='bbox 370 114 390 130'>16</span>
+        <span class='ocrx_word' title='bbox 600 110 660 130'>Salmon</span>
+        <span class='ocrx_word' title='bbox 930 112 950 130'>28</span>
+        <span class='ocrx_word' title='bbox 40 140 90 160'>Mac</span>
+        <span class='ocrx_word' title='bbox 100 140 110 160'>&amp;</span>
+        <span class='ocrx_word' title='bbox 120 140 190 160'>Cheese…</span>
+        <span class='ocrx_word' title='bbox 370 144 390 160'>12</span>
+        <span class='ocrx_word' title='bbox 600 140 650 160'>Steak</span>
+        <span class='ocrx_word' title='bbox 930 142 950 160'>34</span>
+    </div></body></html>"#;
+
+    #[test]
+    fn hocr_words_carry_boxes_and_decoded_text() {
+        let words = parse_hocr(TWO_COLUMNS);
+        assert_eq!(words.len(), 17);
+        assert_eq!(words[0].text, "STARTERS");
+        assert_eq!((words[0].bbox.x0, words[0].bbox.y1), (40.0, 60.0));
+        assert_eq!(words[5].text, "Burger");
+        assert_eq!(words[12].text, "&");
+    }
+
+    #[test]
+    fn two_column_hocr_reads_column_by_column() {
+        let text = layout_text(&parse_hocr(TWO_COLUMNS));
+        assert_eq!(
+            text,
+            "STARTERS\nOysters 18\nBurrata 16\nMac & Cheese 12\n\nMAINS\nBurger 19\nSalmon 28\nSteak 34"
+        );
+    }
+
+    #[test]
+    fn a_column_of_prices_is_not_a_column() {
+        let mut words = Vec::new();
+        let items = [("Oysters", "18"), ("Burrata", "16"), ("Wedge", "11"), ("Fries", "8")];
+        for (n, (name, price)) in items.iter().enumerate() {
+            let y = 40.0 + n as f64 * 30.0;
+            row(&mut words, 40.0, y, &[name, "with", "sauce"]);
+            row(&mut words, 700.0, y, &[price]);
+        }
+        assert!(find_gutters(&words, 20.0).is_empty());
+        assert!(layout_text(&words).starts_with("Oysters with sauce 18\nBurrata with sauce 16"));
+
+        // The same layout with words on the right is two columns
+        let mut columns = Vec::new();
+        for n in 0..4 {
+            let y = 40.0 + n as f64 * 30.0;
+            row(&mut columns, 40.0, y, &["Oysters", "with", "sauce"]);
+            row(&mut columns, 700.0, y, &["Burger", "and", "fries"]);
+        }
+        assert_eq!(find_gutters(&columns, 20.0).len(), 1);
+    }
+
+    #[test]
+    fn inverted_boxes_are_normalized() {
+        let words: Vec<OcrWord> = serde_json::from_str(
+            r#"[{"text": "Oysters", "x0": 0, "y0": 0, "x1": 100, "y1": 20},
+                {"text": "18", "x0": 500, "y0": 20, "x1": 400, "y1": 0}]"#,
+        )
+        .unwrap();
+        assert_eq!((words[1].bbox.x0, words[1].bbox.x1, words[1].bbox.y0), (400.0, 500.0, 0.0));
+        assert_eq!(layout_text(&words), "Oysters 18");
+
+        let hocr = "<span class='ocrx_word' title='bbox 500 20 400 0'>18</span>";
+        let bbox = parse_hocr(hocr)[0].bbox;
+        assert_eq!((bbox.x0, bbox.y0, bbox.x1, bbox.y1), (400.0, 0.0, 500.0, 20.0));
+    }
+
+    #[test]
+    fn lines_group_by_baseline() {
+        let words = vec![
+            word("Oysters", 40.0, 100.0, 110.0, 120.0),
+            // Smaller type on the same baseline
+            word("18", 300.0, 108.0, 316.0, 120.0),
+            // Half a line lower is the next line
+            word("Burrata", 40.0, 131.0, 110.0, 151.0),
+        ];
+        let lines = group_lines(words, 20.0);
+        let texts: Vec<String> = lines.iter().map(Line::text).collect();
+        assert_eq!(texts, ["Oysters 18", "Burrata"]);
+        assert_eq!((lines[0].top, lines[0].bottom), (100.0, 120.0));
+    }
+
+    #[test]
+    fn leaders_are_stripped() {
+        let text = |t: &str| strip_leaders(&word(t, 0.0, 0.0, 10.0, 10.0)).map(|w| w.text);
+        assert_eq!(text("Oysters......").as_deref(), Some("Oysters"));
+        assert_eq!(text("…18").as_deref(), Some("18"));
+        assert_eq!(text("__________"), None);
+        assert_eq!(text("U.S.").as_deref(), Some("U.S."));
+    }
+
+    #[test]
+    fn entities_decode() {
+        assert_eq!(decode_entities("Mac &amp; Cheese"), "Mac & Cheese");
+        assert_eq!(decode_entities("Ros&#233; &#xE9;t&eacute;"), "Rosé ét&eacute;");
+        assert_eq!(decode_entities("AT&T &lt;3"), "AT&T <3");
+    }
+}
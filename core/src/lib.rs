@@ -2,6 +2,53 @@ use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
 use web_sys::Performance;
 
+mod accessibility;
+mod cocktail;
+mod contrast;
+mod diagnostics;
+mod dietary;
+mod facets;
+mod frames;
+mod index;
+mod layout;
+mod menu;
+mod normalize;
+mod preprocess;
+mod price;
+mod quality;
+mod query;
+mod ranking;
+mod schema;
+mod search;
+mod spring;
+mod suggest;
+mod synonyms;
+mod timeline;
+mod typo;
+mod wine;
+
+use accessibility::AccessibilityConfig;
+use contrast::Rgba;
+use frames::FrameSampler;
+use layout::OcrWord;
+use menu::MenuSection;
+use preprocess::PreprocessOptions;
+use quality::{Governor, QualitySettings};
+use spring::{CardPose, CardSprings, SpringConfig};
+use std::collections::HashMap;
+use timeline::{Keyframe, ScrollTimeline};
+
+pub use facets::{FacetCount, Facets, SearchFilters};
+pub use quality::QualityTier;
+pub use query::QueryError;
+pub use ranking::{FieldBoosts, RankingConfig};
+pub use search::{
+    DomainItem, FuzzySearchEngine, MenuEntry, SearchDomain, SearchEngine, SearchHit, SearchItem, SearchRequest, SearchResponse,
+    WineItem,
+};
+pub use suggest::{Completion, CompletionKind};
+pub use synonyms::SynonymConfig;
+
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
@@ -12,48 +59,235 @@ pub fn init_panic_hook() {
     console_error_panic_hook::set_once();
 }
 
+/// Parses a cocktail spec such as "2 oz mezcal, ¾ oz lime, 2 dashes mole bitters"
+/// into measured ingredients and preparation steps
+#[wasm_bindgen]
+pub fn parse_cocktail_spec(spec: &str) -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(&cocktail::parse_spec(spec))
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+/// Checks text on a glass card against a photo background for WCAG contrast
+///
+/// `background_histogram` counts pixels in evenly sized relative-luminance
+/// bins from 0 to 1, sampled from the blurred background behind the card.
+/// Returns the worst-case ratio and, for AA and AAA, the minimum card
+/// opacity, a contrasting tint or a text-shadow halo that would pass.
+#[wasm_bindgen]
+pub fn check_glass_contrast(
+    text_color: &str,
+    card_color: &str,
+    background_histogram: &[f64],
+    large_text: bool,
+) -> Result<JsValue, JsValue> {
+    let text = Rgba::parse(text_color).map_err(|e| JsValue::from_str(&e))?;
+    let card = Rgba::parse(card_color).map_err(|e| JsValue::from_str(&e))?;
+    let report = contrast::check_contrast(text, card, background_histogram, large_text)
+        .map_err(|e| JsValue::from_str(&e))?;
+    serde_wasm_bindgen::to_value(&report)
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+/// Converts extracted menu sections into schema.org `Menu` JSON-LD text for a
+/// `<script type="application/ld+json">` tag; `currency` defaults to USD
+#[wasm_bindgen]
+pub fn menu_to_json_ld(sections: JsValue, name: Option<String>, currency: Option<String>) -> Result<String, JsValue> {
+    let sections: Vec<MenuSection> = serde_wasm_bindgen::from_value(sections)
+        .map_err(|e| JsValue::from_str(&format!("Invalid menu sections: {}", e)))?;
+    let menu = schema::menu_json_ld(&sections, name, currency.as_deref().unwrap_or("USD"));
+    serde_json::to_string(&menu)
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+/// Converts search catalog items into schema.org `Menu` JSON-LD text, with one
+/// `MenuSection` per category
+#[wasm_bindgen]
+pub fn search_items_to_json_ld(items: JsValue, name: Option<String>, currency: Option<String>) -> Result<String, JsValue> {
+    let items: Vec<SearchItem> = serde_wasm_bindgen::from_value(items)
+        .map_err(|e| JsValue::from_str(&format!("Invalid search items: {}", e)))?;
+    let menu = schema::catalog_json_ld(&items, name, currency.as_deref().unwrap_or("USD"));
+    serde_json::to_string(&menu)
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
 /// Glassmorphic renderer for high-performance UI calculations
 #[wasm_bindgen]
 pub struct GlassmorphicRenderer {
-    performance: Performance,
+    /// Effects allowed by the tier after accessibility preferences
+    quality: QualitySettings,
+    tier: QualityTier,
+    accessibility: AccessibilityConfig,
+    timeline: ScrollTimeline,
 }
 
 #[wasm_bindgen]
 impl GlassmorphicRenderer {
-    /// Creates a new GlassmorphicRenderer instance
+    /// Creates a new GlassmorphicRenderer instance, honouring the user's
+    /// motion, transparency and contrast preferences from the start
     #[wasm_bindgen(constructor)]
     pub fn new() -> Result<GlassmorphicRenderer, JsValue> {
         init_panic_hook();
         
         let window = web_sys::window()
             .ok_or_else(|| JsValue::from_str("No window object found"))?;
-        let performance = window.performance()
-            .ok_or_else(|| JsValue::from_str("Performance API not available"))?;
         
-        Ok(GlassmorphicRenderer { performance })
+        let accessibility = detect_accessibility(&window);
+        
+        Ok(GlassmorphicRenderer {
+            quality: accessibility.apply(QualityTier::High.settings()),
+            tier: QualityTier::High,
+            accessibility,
+            timeline: ScrollTimeline::default(),
+        })
+    }
+    
+    /// Sets the effect tier, usually from `QualityGovernor::update`
+    #[wasm_bindgen]
+    pub fn set_quality_tier(&mut self, tier: QualityTier) {
+        self.tier = tier;
+        self.quality = self.accessibility.apply(tier.settings());
+    }
+    
+    /// Current effect tier
+    #[wasm_bindgen]
+    pub fn quality_tier(&self) -> QualityTier {
+        self.tier
+    }
+    
+    /// Sets `{ reduced_motion, reduced_transparency, high_contrast }`; omitted
+    /// flags are off
+    #[wasm_bindgen]
+    pub fn set_accessibility(&mut self, config: JsValue) -> Result<(), JsValue> {
+        let config: AccessibilityConfig = serde_wasm_bindgen::from_value(config)
+            .map_err(|e| JsValue::from_str(&format!("Invalid accessibility config: {}", e)))?;
+        self.accessibility = config;
+        self.quality = config.apply(self.tier.settings());
+        Ok(())
+    }
+    
+    /// Current accessibility flags
+    #[wasm_bindgen]
+    pub fn accessibility(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.accessibility)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+    
+    /// Re-reads the user's preferences from media queries; call from their
+    /// `change` listeners
+    #[wasm_bindgen]
+    pub fn refresh_accessibility(&mut self) {
+        if let Some(window) = web_sys::window() {
+            self.accessibility = detect_accessibility(&window);
+            self.quality = self.accessibility.apply(self.tier.settings());
+        }
+    }
+    
+    /// Alpha for the card's white background tint, 0.05 unless transparency
+    /// is reduced or contrast raised
+    #[wasm_bindgen]
+    pub fn card_opacity(&self) -> f64 {
+        self.quality.card_opacity
+    }
+    
+    /// Number of distinct parallax depths worth rendering at the current tier
+    #[wasm_bindgen]
+    pub fn parallax_layers(&self) -> u32 {
+        self.quality.parallax_layers
+    }
+    
+    /// Whether cards should follow the pointer at the current tier
+    #[wasm_bindgen]
+    pub fn magnetic_hover_enabled(&self) -> bool {
+        self.quality.magnetic_hover
+    }
+    
+    /// Whether hero images should run the Ken Burns pan and zoom
+    #[wasm_bindgen]
+    pub fn ken_burns_enabled(&self) -> bool {
+        self.quality.ken_burns
     }
     
     /// Calculates blur intensity based on scroll position
-    /// Returns a value between 3.0 and 8.0
+    /// Returns a value between 3.0 and 8.0 at full quality, narrower on lower
+    /// tiers, fixed with reduced motion and 0.0 when backdrop blur is off
     #[wasm_bindgen]
     pub fn calculate_blur_intensity(&self, scroll_position: f64, viewport_height: f64) -> f64 {
+        let QualitySettings { blur_min, blur_max, .. } = self.quality;
         if viewport_height <= 0.0 {
-            return 3.0;
+            return blur_min;
         }
-        let normalized = (scroll_position / viewport_height).min(1.0).max(0.0);
-        3.0 + (normalized * 5.0)
+        let normalized = (scroll_position / viewport_height).clamp(0.0, 1.0);
+        blur_min + (normalized * (blur_max - blur_min))
     }
     
     /// Calculates parallax offset for layered backgrounds
+    ///
+    /// Depths are snapped to the tier's layer count so the layers beyond it
+    /// move together; with parallax off or reduced motion every layer stays put.
     #[wasm_bindgen]
     pub fn calculate_parallax_offset(&self, scroll_position: f64, layer_depth: f64) -> f64 {
-        let depth_factor = layer_depth.clamp(0.0, 1.0);
+        let depth_factor = match self.quality.parallax_layers {
+            0 => return 0.0,
+            1 => 0.0,
+            layers => {
+                let steps = f64::from(layers - 1);
+                (layer_depth.clamp(0.0, 1.0) * steps).round() / steps
+            }
+        };
         scroll_position * (0.5 - (depth_factor * 0.1))
     }
     
+    /// Registers a scroll-driven property from keyframes such as
+    /// `[{ offset: 0, value: 1 }, { offset: 0.3, value: 0, easing: "ease-out" }]`;
+    /// `offset` is scroll progress from 0 to 1 and `easing` is any CSS
+    /// `linear`, `ease*`, `cubic-bezier()` or `steps()` curve
+    #[wasm_bindgen]
+    pub fn register_timeline_property(&mut self, name: &str, keyframes: JsValue) -> Result<(), JsValue> {
+        let keyframes: Vec<Keyframe> = serde_wasm_bindgen::from_value(keyframes)
+            .map_err(|e| JsValue::from_str(&format!("Invalid keyframes: {}", e)))?;
+        self.timeline
+            .register(name, keyframes)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+    
+    /// Removes a scroll-driven property
+    #[wasm_bindgen]
+    pub fn remove_timeline_property(&mut self, name: &str) {
+        self.timeline.remove(name);
+    }
+    
+    /// Interpolates every registered property at a scroll position, where
+    /// `scroll_length` is the distance over which progress runs from 0 to 1;
+    /// returns a `{ name: value }` object
+    ///
+    /// With reduced motion nothing moves with scroll, so every property holds
+    /// its first keyframe value, the resting state before any scrolling.
+    #[wasm_bindgen]
+    pub fn sample_timeline(&self, scroll_position: f64, scroll_length: f64) -> Result<JsValue, JsValue> {
+        let values = if self.accessibility.reduced_motion {
+            self.timeline.resting_values()
+        } else {
+            self.timeline.sample(timeline::scroll_progress(scroll_position, scroll_length))
+        };
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        values
+            .serialize(&serializer)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+    
     /// Optimizes card transform for magnetic hover effects
+    /// Returns a resting transform when magnetic hover is off at the current
+    /// tier or the user prefers reduced motion
     #[wasm_bindgen]
     pub fn optimize_card_transform(&self, mouse_x: f64, mouse_y: f64, intensity: f64) -> TransformData {
+        if !self.quality.magnetic_hover {
+            return TransformData {
+                rotate_x: 0.0,
+                rotate_y: 0.0,
+                scale: 1.0,
+                perspective: 1000.0,
+            };
+        }
         let clamped_intensity = intensity.clamp(0.0, 2.0);
         let rotate_x = (mouse_y * clamped_intensity).clamp(-15.0, 15.0);
         let rotate_y = (mouse_x * clamped_intensity).clamp(-15.0, 15.0);
@@ -70,7 +304,7 @@ impl GlassmorphicRenderer {
 
 /// Transform data for card animations
 #[wasm_bindgen]
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct TransformData {
     pub rotate_x: f64,
     pub rotate_y: f64,
@@ -78,6 +312,141 @@ pub struct TransformData {
     pub perspective: f64,
 }
 
+impl From<&TransformData> for CardPose {
+    fn from(transform: &TransformData) -> Self {
+        CardPose {
+            rotate_x: transform.rotate_x,
+            rotate_y: transform.rotate_y,
+            scale: transform.scale,
+            perspective: transform.perspective,
+        }
+    }
+}
+
+impl From<CardPose> for TransformData {
+    fn from(pose: CardPose) -> Self {
+        TransformData {
+            rotate_x: pose.rotate_x,
+            rotate_y: pose.rotate_y,
+            scale: pose.scale,
+            perspective: pose.perspective,
+        }
+    }
+}
+
+/// Damped-spring animator easing each card's transform toward its target
+#[wasm_bindgen]
+pub struct SpringAnimator {
+    config: SpringConfig,
+    cards: HashMap<String, CardSprings>,
+    /// With reduced motion cards jump straight to their targets
+    accessibility: AccessibilityConfig,
+}
+
+#[wasm_bindgen]
+impl SpringAnimator {
+    /// Creates an animator with the default spring (stiffness 170, damping 26,
+    /// mass 1, rest threshold 0.01), honouring the user's motion preference
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SpringAnimator {
+        SpringAnimator {
+            config: SpringConfig::default(),
+            cards: HashMap::new(),
+            accessibility: web_sys::window().map(|window| detect_accessibility(&window)).unwrap_or_default(),
+        }
+    }
+    
+    /// Creates an animator from `{ stiffness, damping, mass, rest_threshold }`;
+    /// omitted fields keep their defaults
+    #[wasm_bindgen]
+    pub fn with_config(config: JsValue) -> Result<SpringAnimator, JsValue> {
+        let config: SpringConfig = serde_wasm_bindgen::from_value(config)
+            .map_err(|e| JsValue::from_str(&format!("Invalid spring config: {}", e)))?;
+        Ok(SpringAnimator {
+            config,
+            ..SpringAnimator::new()
+        })
+    }
+    
+    /// Sets `{ reduced_motion, reduced_transparency, high_contrast }`, as on
+    /// `GlassmorphicRenderer`; with reduced motion every card jumps to its
+    /// target at once
+    #[wasm_bindgen]
+    pub fn set_accessibility(&mut self, config: JsValue) -> Result<(), JsValue> {
+        let config: AccessibilityConfig = serde_wasm_bindgen::from_value(config)
+            .map_err(|e| JsValue::from_str(&format!("Invalid accessibility config: {}", e)))?;
+        self.accessibility = config;
+        if config.reduced_motion {
+            self.cards.values_mut().for_each(CardSprings::jump_to_target);
+        }
+        Ok(())
+    }
+    
+    /// Sets the transform a card should spring toward, typically the result
+    /// of `GlassmorphicRenderer::optimize_card_transform`
+    #[wasm_bindgen]
+    pub fn set_target(&mut self, card_id: &str, target: &TransformData) {
+        let card = self
+            .cards
+            .entry(card_id.to_string())
+            .or_insert_with(|| CardSprings::new(CardPose::REST));
+        card.set_target(target.into());
+        if self.accessibility.reduced_motion {
+            card.jump_to_target();
+        }
+    }
+    
+    /// Springs a card back to its flat resting transform
+    #[wasm_bindgen]
+    pub fn release(&mut self, card_id: &str) {
+        if let Some(card) = self.cards.get_mut(card_id) {
+            card.set_target(CardPose::REST);
+            if self.accessibility.reduced_motion {
+                card.jump_to_target();
+            }
+        }
+    }
+    
+    /// Advances every card by `dt_ms` milliseconds; returns true once all
+    /// cards have settled and no more frames are needed
+    #[wasm_bindgen]
+    pub fn step(&mut self, dt_ms: f64) -> bool {
+        let seconds = dt_ms / 1000.0;
+        let mut settled = true;
+        for card in self.cards.values_mut() {
+            settled &= card.step(seconds, &self.config);
+        }
+        settled
+    }
+    
+    /// Current transform of a card, or the resting transform if it is unknown
+    #[wasm_bindgen]
+    pub fn transform(&self, card_id: &str) -> TransformData {
+        self.cards
+            .get(card_id)
+            .map_or(CardPose::REST, CardSprings::pose)
+            .into()
+    }
+    
+    /// Whether a card has reached its target
+    #[wasm_bindgen]
+    pub fn is_settled(&self, card_id: &str) -> bool {
+        self.cards.get(card_id).is_none_or(CardSprings::is_settled)
+    }
+    
+    /// Forgets a card, e.g. when it is removed from the page
+    #[wasm_bindgen]
+    pub fn remove(&mut self, card_id: &str) {
+        self.cards.remove(card_id);
+    }
+}
+
+impl Default for SpringAnimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// OCR processor for menu digitization
 #[wasm_bindgen]
 pub struct MenuOCRProcessor;
@@ -91,46 +460,88 @@ impl MenuOCRProcessor {
     }
     
     /// Preprocesses image data for optimal OCR results
+    /// Decodes PNG/JPEG, binarizes, deskews, despeckles and crops, returning a PNG
     #[wasm_bindgen]
-    pub fn preprocess_image(&self, image_data: &[u8]) -> Vec<u8> {
-        // TODO: Implement image preprocessing
-        // For now, return a copy of the input
-        image_data.to_vec()
+    pub fn preprocess_image(&self, image_data: &[u8]) -> Result<Vec<u8>, JsValue> {
+        preprocess::preprocess(image_data, &PreprocessOptions::default())
+            .map_err(|e| JsValue::from_str(&e))
+    }
+    
+    /// Preprocesses image data with custom thresholding and cleanup options
+    #[wasm_bindgen]
+    pub fn preprocess_image_with_options(&self, image_data: &[u8], options: JsValue) -> Result<Vec<u8>, JsValue> {
+        let options: PreprocessOptions = serde_wasm_bindgen::from_value(options)
+            .map_err(|e| JsValue::from_str(&format!("Invalid options: {}", e)))?;
+        preprocess::preprocess(image_data, &options)
+            .map_err(|e| JsValue::from_str(&e))
     }
     
     /// Extracts menu structure from OCR text
-    #[wasm_bindgen(catch)]
+    /// Every section and item carries its `confidence` and source `lines`; the
+    /// result stays a bare array of sections for existing callers, so the
+    /// warnings that go with it come from `extract_menu_with_diagnostics`
+    #[wasm_bindgen]
     pub fn extract_menu_structure(&self, ocr_text: &str) -> Result<JsValue, JsValue> {
         let sections = self.parse_menu_sections(ocr_text);
         serde_wasm_bindgen::to_value(&sections)
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
     }
     
+    /// Extracts menu structure from OCR text as `{ sections, warnings }`, the
+    /// same scored sections as `extract_menu_structure` plus warnings that
+    /// cite the source line range
+    #[wasm_bindgen]
+    pub fn extract_menu_with_diagnostics(&self, ocr_text: &str) -> Result<JsValue, JsValue> {
+        let extraction = menu::extract_menu(ocr_text);
+        serde_wasm_bindgen::to_value(&extraction)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+    
+    /// Extracts a wine list from OCR text, splitting each line into producer,
+    /// name, varietal, appellation, region and vintage
+    #[wasm_bindgen]
+    pub fn extract_wine_list(&self, ocr_text: &str) -> Result<JsValue, JsValue> {
+        let wines = wine::wine_sections(self.parse_menu_sections(ocr_text));
+        serde_wasm_bindgen::to_value(&wines)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+    
+    /// Extracts menu structure from hOCR output, using word positions to
+    /// separate columns and pair prices with their items
+    #[wasm_bindgen]
+    pub fn extract_menu_structure_from_hocr(&self, hocr: &str) -> Result<JsValue, JsValue> {
+        let words = layout::parse_hocr(hocr);
+        let sections = self.parse_menu_sections(&layout::layout_text(&words));
+        serde_wasm_bindgen::to_value(&sections)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+    
+    /// Extracts menu structure from a list of words with bounding boxes,
+    /// either `{ text, bbox: { x0, y0, x1, y1 } }` or flat `{ text, x0, y0, x1, y1 }`
+    #[wasm_bindgen]
+    pub fn extract_menu_structure_from_words(&self, words: JsValue) -> Result<JsValue, JsValue> {
+        let words: Vec<OcrWord> = serde_wasm_bindgen::from_value(words)
+            .map_err(|e| JsValue::from_str(&format!("Invalid word list: {}", e)))?;
+        let sections = self.parse_menu_sections(&layout::layout_text(&words));
+        serde_wasm_bindgen::to_value(&sections)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+    
     fn parse_menu_sections(&self, text: &str) -> Vec<MenuSection> {
-        // TODO: Implement advanced menu parsing logic
-        // This is a placeholder implementation
-        vec![]
+        menu::parse_menu_text(text)
     }
 }
 
-#[derive(Serialize, Deserialize)]
-struct MenuSection {
-    title: String,
-    items: Vec<MenuItem>,
-}
-
-#[derive(Serialize, Deserialize)]
-struct MenuItem {
-    name: String,
-    description: String,
-    price: f64,
-    dietary_tags: Vec<String>,
+impl Default for MenuOCRProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// Performance monitor for tracking frame rates and optimization
 #[wasm_bindgen]
 pub struct PerformanceMonitor {
-    start_time: f64,
+    frames: FrameSampler,
     performance: Option<Performance>,
 }
 
@@ -139,41 +550,124 @@ impl PerformanceMonitor {
     /// Creates a new PerformanceMonitor instance
     #[wasm_bindgen(constructor)]
     pub fn new() -> PerformanceMonitor {
-        let (performance, start_time) = web_sys::window()
-            .and_then(|w| w.performance())
-            .map(|p| {
-                let time = p.now();
-                (Some(p), time)
-            })
-            .unwrap_or((None, 0.0));
-        
+        PerformanceMonitor::with_capacity(frames::DEFAULT_CAPACITY)
+    }
+    
+    /// Creates a monitor that keeps statistics over the last `capacity` frames
+    #[wasm_bindgen]
+    pub fn with_capacity(capacity: usize) -> PerformanceMonitor {
         PerformanceMonitor {
-            start_time,
-            performance,
+            frames: FrameSampler::new(capacity),
+            performance: web_sys::window().and_then(|w| w.performance()),
         }
     }
     
-    /// Measures current frames per second
+    /// Records one frame; pass the `requestAnimationFrame` timestamp, or
+    /// nothing to use `performance.now()`
     #[wasm_bindgen]
-    pub fn measure_fps(&self) -> f64 {
-        match &self.performance {
-            Some(perf) => {
-                let current = perf.now();
-                let delta = current - self.start_time;
-                if delta > 0.0 {
-                    1000.0 / delta
-                } else {
-                    60.0 // Default to 60 FPS
-                }
-            }
-            None => 60.0 // Default when Performance API unavailable
+    pub fn record_frame(&mut self, timestamp: Option<f64>) {
+        let timestamp = timestamp.or_else(|| self.performance.as_ref().map(|p| p.now()));
+        if let Some(timestamp) = timestamp {
+            self.frames.record(timestamp);
         }
     }
     
+    /// Call when the animation loop stops so the idle gap isn't counted
+    #[wasm_bindgen]
+    pub fn pause(&mut self) {
+        self.frames.pause();
+    }
+    
+    /// Discards all recorded frames
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.frames.clear();
+    }
+    
+    /// Measures current frames per second over the recorded window
+    #[wasm_bindgen]
+    pub fn measure_fps(&self) -> f64 {
+        self.frames.fps().unwrap_or(60.0) // Default to 60 FPS until frames are recorded
+    }
+    
     /// Checks if animation frame rate is acceptable
     #[wasm_bindgen]
     pub fn optimize_animation_frame(&self, target_fps: f64) -> bool {
         let current_fps = self.measure_fps();
         current_fps >= target_fps * 0.95
     }
-}
\ No newline at end of file
+    
+    /// Returns rolling FPS, p50/p95/p99 frame times, long-frame count and
+    /// dropped-frame ratio for the recorded window
+    #[wasm_bindgen]
+    pub fn snapshot(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.frames.snapshot())
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+}
+
+/// Reads motion, transparency and contrast preferences from media queries;
+/// `forced-colors` counts as high contrast
+fn detect_accessibility(window: &web_sys::Window) -> AccessibilityConfig {
+    let matches = |query: &str| {
+        window
+            .match_media(query)
+            .ok()
+            .flatten()
+            .is_some_and(|list| list.matches())
+    };
+    AccessibilityConfig {
+        reduced_motion: matches("(prefers-reduced-motion: reduce)"),
+        reduced_transparency: matches("(prefers-reduced-transparency: reduce)"),
+        high_contrast: matches("(prefers-contrast: more)") || matches("(forced-colors: active)"),
+    }
+}
+
+impl Default for PerformanceMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Steps visual effect tiers down when frames run over budget and back up
+/// with hysteresis once the device has headroom
+#[wasm_bindgen]
+pub struct QualityGovernor {
+    governor: Governor,
+}
+
+#[wasm_bindgen]
+impl QualityGovernor {
+    /// Creates a governor targeting `target_fps`, starting at the highest tier
+    #[wasm_bindgen(constructor)]
+    pub fn new(target_fps: f64) -> QualityGovernor {
+        QualityGovernor {
+            governor: Governor::new(target_fps, QualityTier::High),
+        }
+    }
+    
+    /// Judges the frames the monitor recorded since the last update, about
+    /// once a second; returns true when the tier changed
+    ///
+    /// Each frame is judged once, so one slow second counts once and the
+    /// judgement after a change only sees frames drawn at the new tier.
+    #[wasm_bindgen]
+    pub fn update(&mut self, monitor: &mut PerformanceMonitor) -> bool {
+        monitor
+            .frames
+            .take_recent(quality::MIN_FRAMES)
+            .is_some_and(|snapshot| self.governor.evaluate(&snapshot).is_some())
+    }
+    
+    /// Current effect tier
+    #[wasm_bindgen]
+    pub fn tier(&self) -> QualityTier {
+        self.governor.tier()
+    }
+    
+    /// Forces a tier, e.g. from a saved preference
+    #[wasm_bindgen]
+    pub fn set_tier(&mut self, tier: QualityTier) {
+        self.governor.set_tier(tier);
+    }
+}
@@ -0,0 +1,408 @@
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics::{diagnose, garbled_price, ExtractionWarning, SourceLines};
+use crate::dietary::{hoist_trailing_markers, tag_sections, DietaryLegend};
+use crate::price::{split_trailing_prices, PriceClause, PricePoint};
+
+/// A titled group of items on a printed menu
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct MenuSection {
+    pub(crate) title: String,
+    pub(crate) items: Vec<MenuItem>,
+    /// 0.0 to 1.0, how sure the parser is this section was read correctly
+    pub(crate) confidence: f64,
+    pub(crate) lines: SourceLines,
+}
+
+/// A single dish or drink extracted from menu text
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct MenuItem {
+    pub(crate) name: String,
+    pub(crate) description: String,
+    /// First printed price, kept for single-price consumers
+    pub(crate) price: f64,
+    /// Every labeled or unlabeled price on the line
+    pub(crate) prices: Vec<PricePoint>,
+    pub(crate) market_price: bool,
+    pub(crate) glass_price: Option<f64>,
+    pub(crate) bottle_price: Option<f64>,
+    pub(crate) dietary_tags: Vec<String>,
+    /// 0.0 to 1.0, how sure the parser is this item was read correctly
+    pub(crate) confidence: f64,
+    pub(crate) lines: SourceLines,
+}
+
+/// Sections together with the warnings raised while reading them
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct MenuExtraction {
+    pub(crate) sections: Vec<MenuSection>,
+    pub(crate) warnings: Vec<ExtractionWarning>,
+}
+
+/// Item being assembled while its wrapped lines are read
+struct PendingItem {
+    name: String,
+    description: String,
+    price: Option<PriceClause>,
+    /// The line ended in a price that couldn't be read
+    misread_price: bool,
+    lines: SourceLines,
+}
+
+impl PendingItem {
+    fn from_text(text: &str, price: Option<PriceClause>, index: usize) -> PendingItem {
+        let (name, description) = split_name_description(text);
+        PendingItem {
+            name,
+            description,
+            price,
+            misread_price: false,
+            lines: SourceLines::at(index),
+        }
+    }
+
+    fn append_description(&mut self, text: &str) {
+        let text = collapse_whitespace(text);
+        if text.is_empty() {
+            return;
+        }
+        if self.description.is_empty() {
+            self.description = text;
+        } else if self.description.ends_with('-') {
+            // Word wrapped across lines with a hyphen: "slow-" + "roasted"
+            self.description.push_str(&text);
+        } else {
+            self.description.push(' ');
+            self.description.push_str(&text);
+        }
+    }
+
+    fn into_item(self) -> MenuItem {
+        let clause = self.price.unwrap_or_default();
+        MenuItem {
+            name: self.name,
+            description: self.description,
+            price: clause.primary().unwrap_or(0.0),
+            glass_price: clause.glass_price(),
+            bottle_price: clause.bottle_price(),
+            market_price: clause.market_price,
+            prices: clause.points,
+            dietary_tags: Vec::new(),
+            confidence: 1.0,
+            lines: self.lines,
+        }
+    }
+}
+
+/// Parses raw OCR text into menu sections and items
+///
+/// Headings are detected from case and blank-line spacing, description lines
+/// that wrap under an item are joined onto it, and trailing prices are pulled
+/// into `MenuItem::prices`. Dietary markers are resolved against the menu's
+/// own legend and footnotes into `MenuItem::dietary_tags`.
+pub(crate) fn parse_menu_text(text: &str) -> Vec<MenuSection> {
+    extract_menu(text).sections
+}
+
+/// Parses menu text like `parse_menu_text`, scoring each section and item and
+/// collecting warnings that point back at 1-based line numbers in `text`
+pub(crate) fn extract_menu(text: &str) -> MenuExtraction {
+    let mut cleaned: Vec<String> = text
+        .lines()
+        .map(|line| hoist_trailing_markers(clean_line(line)))
+        .collect();
+    let legend = DietaryLegend::extract(&mut cleaned);
+    let lines: Vec<&str> = cleaned.iter().map(String::as_str).collect();
+
+    // Sections are only kept once they hold items, so banners such as the
+    // restaurant name don't turn into empty sections
+    let mut sections: Vec<MenuSection> = Vec::new();
+    let mut current = MenuSection::default();
+    let mut pending: Option<PendingItem> = None;
+    let mut blank_before = true;
+
+    for (i, line) in lines.iter().enumerate() {
+        if is_blank(line) {
+            blank_before = true;
+            continue;
+        }
+
+        // Letter-spaced headings may carry digits ("T A B L E  1 8 3 7"), so
+        // they are recognised before a trailing digit is taken as a price
+        let (text, price, heading) = match collapse_letter_spacing(&strip_decoration(line)) {
+            Some(title) => (line.to_string(), None, Some(title)),
+            None => {
+                let (text, price) = split_trailing_prices(line, is_drinks_heading(&current.title));
+                let heading = match price {
+                    Some(_) => None,
+                    None => heading_title(&lines, i, blank_before),
+                };
+                (text, price, heading)
+            }
+        };
+
+        if let Some(title) = heading {
+            if let Some(item) = pending.take() {
+                current.items.push(item.into_item());
+            }
+            if !current.items.is_empty() {
+                sections.push(current);
+            }
+            current = MenuSection {
+                title,
+                lines: SourceLines::at(i),
+                ..MenuSection::default()
+            };
+            blank_before = false;
+            continue;
+        }
+
+        // A misread price still marks the end of an item line; the token is
+        // dropped from the name and reported by `diagnose`
+        let (text, garbled) = match price.is_none().then(|| garbled_price(&text)).flatten() {
+            Some(token) => (text[..text.len() - token.len()].trim_end().to_string(), true),
+            None => (text, false),
+        };
+
+        match pending.as_mut() {
+            // Name on one line, description and price on the next
+            Some(item) if !blank_before && price.is_some() && item.price.is_none() && !item.misread_price => {
+                item.append_description(&text);
+                item.price = price;
+                item.lines.extend_to(i);
+            }
+            // Description wrapping under the item above
+            Some(item) if !blank_before && price.is_none() && !garbled && !starts_new_item(&lines, i, &text, item) => {
+                item.append_description(&text);
+                item.lines.extend_to(i);
+            }
+            _ => {
+                if let Some(item) = pending.take() {
+                    current.items.push(item.into_item());
+                }
+                if current.items.is_empty() && current.title.is_empty() {
+                    current.lines = SourceLines::at(i);
+                }
+                let mut item = PendingItem::from_text(&text, price, i);
+                item.misread_price = garbled;
+                pending = Some(item);
+            }
+        }
+        current.lines.extend_to(i);
+        blank_before = false;
+    }
+
+    if let Some(item) = pending.take() {
+        current.items.push(item.into_item());
+    }
+    if !current.items.is_empty() {
+        sections.push(current);
+    }
+
+    tag_sections(&mut sections, &legend);
+    let warnings = diagnose(&mut sections, &lines);
+    MenuExtraction { sections, warnings }
+}
+
+/// Returns the heading title if the line at `index` is a section heading
+fn heading_title(lines: &[&str], index: usize, blank_before: bool) -> Option<String> {
+    let raw = lines[index];
+    let title = strip_decoration(raw);
+    if title.chars().filter(|c| c.is_alphabetic()).count() < 2 {
+        return None;
+    }
+    if title.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let next = next_line(lines, index);
+    let blank_after = next.is_none_or(is_blank);
+
+    let explicit = raw.trim_end().ends_with(':');
+    if explicit || is_uppercase(&title) {
+        // An upper-case line followed directly by a lower-case line is an
+        // item name with its description wrapped underneath
+        if !explicit && next.is_some_and(|n| !is_blank(n) && starts_lowercase(n)) {
+            return None;
+        }
+        return Some(collapse_whitespace(&title));
+    }
+
+    // Short title-case line standing on its own between blank lines
+    if blank_before && blank_after && is_title_case(&title) {
+        return Some(collapse_whitespace(&title));
+    }
+
+    None
+}
+
+/// Decides whether an unpriced line begins a new item rather than continuing
+/// the description of `item`
+fn starts_new_item(lines: &[&str], index: usize, text: &str, item: &PendingItem) -> bool {
+    if item.price.is_none() || starts_lowercase(text) {
+        return false;
+    }
+    // A capitalised line is the next item's name when the line right below it
+    // is that item's lower-case description; an all-caps name needs no more
+    // evidence, a title-case one only counts if the description carries the
+    // price
+    match next_line(lines, index) {
+        Some(next) if !is_blank(next) && starts_lowercase(next) => {
+            is_uppercase(text) || split_trailing_prices(next, false).1.is_some()
+        }
+        _ => false,
+    }
+}
+
+/// Splits "Wings - buffalo or bbq" into a name and a description
+fn split_name_description(text: &str) -> (String, String) {
+    let text = collapse_whitespace(text);
+    for separator in [" — ", " – ", " - ", ": "] {
+        if let Some((name, description)) = text.split_once(separator) {
+            let name = name.trim();
+            if !name.is_empty() {
+                return (name.to_string(), description.trim().to_string());
+            }
+        }
+    }
+    (text, String::new())
+}
+
+/// Strips ornamental characters and a trailing colon from a heading
+fn strip_decoration(text: &str) -> String {
+    let ornament = |c: char| {
+        c.is_whitespace() || matches!(c, '-' | '–' | '—' | '=' | '*' | '~' | '•' | '·' | '_' | '#' | ':')
+    };
+    text.trim_matches(ornament).to_string()
+}
+
+/// Collapses "S T A R T E R S" into "STARTERS", keeping wide gaps as word breaks
+fn collapse_letter_spacing(text: &str) -> Option<String> {
+    let letters = text.split_whitespace().collect::<Vec<_>>();
+    if letters.len() < 3 || !letters.iter().all(|l| l.chars().count() == 1) {
+        return None;
+    }
+
+    let words: Vec<String> = text
+        .split("  ")
+        .map(|word| word.split_whitespace().collect::<String>())
+        .filter(|word| !word.is_empty())
+        .collect();
+    Some(words.join(" "))
+}
+
+/// Wine and beer headings, under which two bare prices are a glass and a
+/// bottle (or pint and pitcher) rather than a count and a price
+fn is_drinks_heading(title: &str) -> bool {
+    let title = title.to_lowercase();
+    title.contains("glass")
+        || title.split(|c: char| !c.is_alphanumeric()).any(|word| {
+            matches!(
+                word,
+                "wine" | "wines" | "vino" | "vins" | "red" | "reds" | "white" | "whites" | "rosé" | "rose"
+                    | "sparkling" | "bubbles" | "champagne" | "beer" | "beers" | "draft" | "draught" | "taps"
+                    | "cider" | "sake" | "btg"
+            )
+        })
+}
+
+fn is_title_case(text: &str) -> bool {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.is_empty() || words.len() > 4 {
+        return false;
+    }
+    if text.contains([',', '.', ';']) {
+        return false;
+    }
+    words.iter().all(|word| {
+        matches!(*word, "&" | "and" | "of" | "the" | "a" | "on" | "to" | "from")
+            || word.starts_with(|c: char| c.is_uppercase())
+    })
+}
+
+fn is_uppercase(text: &str) -> bool {
+    text.chars().any(|c| c.is_alphabetic()) && !text.chars().any(|c| c.is_lowercase())
+}
+
+fn starts_lowercase(text: &str) -> bool {
+    text.trim_start()
+        .chars()
+        .find(|c| c.is_alphanumeric())
+        .is_some_and(|c| c.is_lowercase())
+}
+
+fn next_line<'a>(lines: &[&'a str], index: usize) -> Option<&'a str> {
+    lines.get(index + 1).copied()
+}
+
+/// Lines without any letters or digits are separators or OCR noise
+fn is_blank(line: &str) -> bool {
+    !line.chars().any(|c| c.is_alphanumeric())
+}
+
+fn clean_line(line: &str) -> &str {
+    line.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}')
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(section: &MenuSection) -> Vec<&str> {
+        section.items.iter().map(|item| item.name.as_str()).collect()
+    }
+
+    #[test]
+    fn headings_start_sections() {
+        let sections = parse_menu_text("STARTERS\nOysters 18\nBurrata 16\n\nMains:\nBurger 19\nSalmon 28");
+        let titles: Vec<&str> = sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, ["STARTERS", "Mains"]);
+        assert_eq!(names(&sections[0]), ["Oysters", "Burrata"]);
+        assert_eq!(sections[1].items[1].price, 28.0);
+    }
+
+    #[test]
+    fn wrapped_descriptions_join_the_item_above() {
+        let sections = parse_menu_text(
+            "MAINS\nSteak Frites 34\nhanger steak, herb butter, slow-\nroasted shallots\nBurger 19",
+        );
+        let steak = &sections[0].items[0];
+        assert_eq!(steak.name, "Steak Frites");
+        assert_eq!(steak.description, "hanger steak, herb butter, slow-roasted shallots");
+        assert_eq!(names(&sections[0]), ["Steak Frites", "Burger"]);
+    }
+
+    #[test]
+    fn letter_spaced_headings_collapse() {
+        let sections = parse_menu_text("S T A R T E R S\nOysters 18\n\nR A W  B A R\nClams 14");
+        assert_eq!(sections[0].title, "STARTERS");
+        assert_eq!(sections[1].title, "RAW BAR");
+        assert_eq!(collapse_letter_spacing("T A B L E  1 8 3 7").as_deref(), Some("TABLE 1837"));
+        assert_eq!(collapse_letter_spacing("Old Fashioned"), None);
+    }
+
+    #[test]
+    fn names_split_from_descriptions_on_separators() {
+        let sections = parse_menu_text("SNACKS\nWings - buffalo or bbq 14\nFries: duck fat, aioli 8");
+        let items = &sections[0].items;
+        assert_eq!((items[0].name.as_str(), items[0].description.as_str()), ("Wings", "buffalo or bbq"));
+        assert_eq!((items[1].name.as_str(), items[1].description.as_str()), ("Fries", "duck fat, aioli"));
+        assert_eq!(split_name_description("Negroni"), ("Negroni".to_string(), String::new()));
+    }
+
+    #[test]
+    fn bare_price_pairs_only_split_on_drinks_lists() {
+        let sections = parse_menu_text("SNACKS\nWings 6 12\n\nWINES\nPinot Noir 12 46");
+        let wings = &sections[0].items[0];
+        assert_eq!((wings.name.as_str(), wings.price, wings.glass_price), ("Wings 6", 12.0, None));
+        let pinot = &sections[1].items[0];
+        assert_eq!(pinot.name, "Pinot Noir");
+        assert_eq!((pinot.glass_price, pinot.bottle_price), (Some(12.0), Some(46.0)));
+    }
+}
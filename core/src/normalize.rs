@@ -0,0 +1,58 @@
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Letters NFKD leaves whole, spelled out the way guests type them
+fn ligature(c: char) -> Option<&'static str> {
+    let folded = match c {
+        'æ' | 'Æ' => "ae",
+        'œ' | 'Œ' => "oe",
+        'ß' | 'ẞ' => "ss",
+        'ø' | 'Ø' => "o",
+        'đ' | 'Đ' | 'ð' | 'Ð' => "d",
+        'ł' | 'Ł' => "l",
+        'þ' | 'Þ' => "th",
+        'ı' => "i",
+        _ => return None,
+    };
+    Some(folded)
+}
+
+/// Smart quotes, primes, dashes and the fraction slash as their ASCII
+/// counterparts; every other character is returned unchanged, so offsets
+/// into folded text still line up with the original
+pub(crate) fn fold_punctuation(c: char) -> char {
+    match c {
+        '‘' | '’' | '‚' | '‛' | '′' | '`' | '´' => '\'',
+        '“' | '”' | '„' | '‟' | '″' | '«' | '»' => '"',
+        '‐' | '‑' | '‒' | '–' | '—' | '―' | '−' | '﹣' | '－' => '-',
+        '⁄' | '∕' => '/',
+        _ => c,
+    }
+}
+
+/// The lower-case, accent-free form catalog text and queries are compared
+/// in, so "rose" matches "Rosé" and "oeil" matches "Œil"
+///
+/// Applies NFKD, which also splits ligatures like "ﬁ" and turns "½" into
+/// "1/2", then drops the combining marks it separated out. A fraction right
+/// after a digit starts a new word, so "1½" reads "1 1/2" rather than "11/2".
+pub(crate) fn fold(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for original in text.chars() {
+        if is_vulgar_fraction(original) && folded.ends_with(|c: char| c.is_ascii_digit()) {
+            folded.push(' ');
+        }
+        for c in original.nfkd().filter(|&c| !is_combining_mark(c)) {
+            match ligature(c) {
+                Some(letters) => folded.push_str(letters),
+                None => folded.extend(fold_punctuation(c).to_lowercase()),
+            }
+        }
+    }
+    folded
+}
+
+/// "½", "⅞" and the like, which NFKD spells out around a fraction slash
+fn is_vulgar_fraction(c: char) -> bool {
+    c != '\u{2044}' && c.nfkd().any(|d| d == '\u{2044}')
+}
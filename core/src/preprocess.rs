@@ -0,0 +1,530 @@
+use image::imageops::FilterType;
+use image::{GrayImage, ImageOutputFormat, Luma};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+/// Thresholding strategy used to separate ink from paper
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Binarization {
+    /// Local threshold from the mean and deviation around each pixel,
+    /// robust to the uneven lighting of phone photos
+    Sauvola,
+    /// Single global threshold, cheaper and fine for flatbed scans
+    Otsu,
+}
+
+/// Tuning knobs for the OCR preprocessing pipeline
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct PreprocessOptions {
+    pub(crate) binarization: Binarization,
+    /// Sauvola sensitivity; higher values thin the strokes
+    pub(crate) sauvola_k: f64,
+    /// Sauvola window as a fraction of the shorter image side
+    pub(crate) window_ratio: f64,
+    /// Images are downscaled so their longer side fits within this
+    pub(crate) max_dimension: u32,
+    /// Largest skew corrected, in degrees either way
+    pub(crate) max_skew_degrees: f64,
+    /// Ink blobs at or below this many pixels are treated as speckle
+    pub(crate) speckle_area: usize,
+    /// Paper kept around the text when cropping margins
+    pub(crate) margin: u32,
+}
+
+impl Default for PreprocessOptions {
+    fn default() -> Self {
+        PreprocessOptions {
+            binarization: Binarization::Sauvola,
+            sauvola_k: 0.34,
+            window_ratio: 1.0 / 40.0,
+            max_dimension: 2000,
+            max_skew_degrees: 15.0,
+            speckle_area: 6,
+            margin: 16,
+        }
+    }
+}
+
+/// Steepest skew the deskew search will consider, in degrees either way
+const MAX_SKEW_LIMIT: f64 = 45.0;
+
+impl PreprocessOptions {
+    /// Rejects values the pipeline can't work with, such as a skew range
+    /// so wide the angle search never finishes
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if !(self.sauvola_k.is_finite() && (0.0..=1.0).contains(&self.sauvola_k)) {
+            return Err(format!("sauvola_k must be between 0 and 1, got {}", self.sauvola_k));
+        }
+        if !(self.window_ratio.is_finite() && self.window_ratio > 0.0 && self.window_ratio <= 1.0) {
+            return Err(format!("window_ratio must be above 0 and at most 1, got {}", self.window_ratio));
+        }
+        if self.max_dimension == 0 {
+            return Err("max_dimension must be above 0".to_string());
+        }
+        if !(self.max_skew_degrees.is_finite() && (0.0..=MAX_SKEW_LIMIT).contains(&self.max_skew_degrees)) {
+            return Err(format!(
+                "max_skew_degrees must be between 0 and {}, got {}",
+                MAX_SKEW_LIMIT, self.max_skew_degrees
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Black-and-white image where `true` marks ink
+struct Bitmap {
+    width: usize,
+    height: usize,
+    ink: Vec<bool>,
+}
+
+impl Bitmap {
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.ink[y * self.width + x]
+    }
+
+    fn to_gray(&self) -> GrayImage {
+        GrayImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            if self.get(x as usize, y as usize) {
+                Luma([0])
+            } else {
+                Luma([255])
+            }
+        })
+    }
+}
+
+/// Runs the full cleanup pipeline on an encoded PNG or JPEG and returns a
+/// binarized, deskewed and cropped PNG ready for OCR
+pub(crate) fn preprocess(image_data: &[u8], options: &PreprocessOptions) -> Result<Vec<u8>, String> {
+    options.validate().map_err(|e| format!("Invalid options: {}", e))?;
+    let decoded = image::load_from_memory(image_data)
+        .map_err(|e| format!("Image decode error: {}", e))?;
+
+    let decoded = if decoded.width().max(decoded.height()) > options.max_dimension {
+        decoded.resize(options.max_dimension, options.max_dimension, FilterType::Triangle)
+    } else {
+        decoded
+    };
+    let gray = decoded.to_luma8();
+
+    let mut bitmap = match options.binarization {
+        Binarization::Sauvola => sauvola(&gray, options.sauvola_k, options.window_ratio),
+        Binarization::Otsu => threshold(&gray, otsu_threshold(&gray)),
+    };
+
+    clear_border_blobs(&mut bitmap);
+    let angle = estimate_skew(&bitmap, options.max_skew_degrees);
+    if angle.abs() >= 0.1 {
+        bitmap = rotate(&bitmap, -angle);
+    }
+    remove_speckle(&mut bitmap, options.speckle_area);
+    let bitmap = crop_to_content(&bitmap, options.margin as usize);
+
+    let mut output = Cursor::new(Vec::new());
+    bitmap
+        .to_gray()
+        .write_to(&mut output, ImageOutputFormat::Png)
+        .map_err(|e| format!("Image encode error: {}", e))?;
+    Ok(output.into_inner())
+}
+
+/// Global threshold that best splits the histogram into two classes
+fn otsu_threshold(gray: &GrayImage) -> u8 {
+    let mut histogram = [0u64; 256];
+    for pixel in gray.pixels() {
+        histogram[pixel[0] as usize] += 1;
+    }
+
+    let total = gray.pixels().len() as f64;
+    let weighted_total: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(value, &count)| value as f64 * count as f64)
+        .sum();
+
+    let mut background_weight = 0.0;
+    let mut background_sum = 0.0;
+    let mut best = (0u8, 0.0);
+    for (value, &count) in histogram.iter().enumerate() {
+        background_weight += count as f64;
+        if background_weight == 0.0 {
+            continue;
+        }
+        let foreground_weight = total - background_weight;
+        if foreground_weight == 0.0 {
+            break;
+        }
+        background_sum += value as f64 * count as f64;
+        let background_mean = background_sum / background_weight;
+        let foreground_mean = (weighted_total - background_sum) / foreground_weight;
+        let between = background_weight * foreground_weight * (background_mean - foreground_mean).powi(2);
+        if between > best.1 {
+            best = (value as u8, between);
+        }
+    }
+    best.0
+}
+
+fn threshold(gray: &GrayImage, level: u8) -> Bitmap {
+    Bitmap {
+        width: gray.width() as usize,
+        height: gray.height() as usize,
+        ink: gray.pixels().map(|p| p[0] <= level).collect(),
+    }
+}
+
+/// Sauvola thresholding using integral images so each window is O(1)
+fn sauvola(gray: &GrayImage, k: f64, window_ratio: f64) -> Bitmap {
+    const DYNAMIC_RANGE: f64 = 128.0;
+
+    let width = gray.width() as usize;
+    let height = gray.height() as usize;
+    let stride = width + 1;
+
+    let mut sum = vec![0u64; stride * (height + 1)];
+    let mut squares = vec![0u64; stride * (height + 1)];
+    for y in 0..height {
+        let mut row_sum = 0u64;
+        let mut row_squares = 0u64;
+        for x in 0..width {
+            let value = gray.get_pixel(x as u32, y as u32)[0] as u64;
+            row_sum += value;
+            row_squares += value * value;
+            sum[(y + 1) * stride + x + 1] = sum[y * stride + x + 1] + row_sum;
+            squares[(y + 1) * stride + x + 1] = squares[y * stride + x + 1] + row_squares;
+        }
+    }
+
+    let half = ((width.min(height) as f64 * window_ratio) as usize / 2).max(7);
+    let mut ink = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let top = y.saturating_sub(half);
+        let bottom = (y + half + 1).min(height);
+        for x in 0..width {
+            let left = x.saturating_sub(half);
+            let right = (x + half + 1).min(width);
+            let area = ((bottom - top) * (right - left)) as f64;
+
+            let window_sum = (sum[bottom * stride + right] + sum[top * stride + left]
+                - sum[top * stride + right]
+                - sum[bottom * stride + left]) as f64;
+            let window_squares = (squares[bottom * stride + right] + squares[top * stride + left]
+                - squares[top * stride + right]
+                - squares[bottom * stride + left]) as f64;
+
+            let mean = window_sum / area;
+            let deviation = (window_squares / area - mean * mean).max(0.0).sqrt();
+            let level = mean * (1.0 + k * (deviation / DYNAMIC_RANGE - 1.0));
+            ink.push((gray.get_pixel(x as u32, y as u32)[0] as f64) <= level);
+        }
+    }
+
+    Bitmap { width, height, ink }
+}
+
+/// Finds the rotation that makes text rows line up with the x axis by
+/// maximising the sharpness of the horizontal projection profile
+fn estimate_skew(bitmap: &Bitmap, max_degrees: f64) -> f64 {
+    // Sampling a bounded number of ink pixels keeps large photos cheap
+    let ink: Vec<(f64, f64)> = bitmap
+        .ink
+        .iter()
+        .enumerate()
+        .filter(|(_, &ink)| ink)
+        .map(|(i, _)| ((i % bitmap.width) as f64, (i / bitmap.width) as f64))
+        .collect();
+    if ink.len() < 100 {
+        return 0.0;
+    }
+    let step = (ink.len() / 40_000).max(1);
+    let samples: Vec<(f64, f64)> = ink.into_iter().step_by(step).collect();
+
+    let diagonal = ((bitmap.width.pow(2) + bitmap.height.pow(2)) as f64).sqrt();
+    let profile_score = |degrees: f64| -> f64 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let mut bins = vec![0u32; 2 * diagonal as usize + 2];
+        for &(x, y) in &samples {
+            let row = y * cos - x * sin + diagonal;
+            bins[row as usize] += 1;
+        }
+        bins.iter().map(|&count| (count as f64).powi(2)).sum()
+    };
+
+    let search = |from: f64, to: f64, step: f64| -> f64 {
+        let mut best = (0.0, f64::MIN);
+        let mut angle = from;
+        while angle <= to + f64::EPSILON {
+            let score = profile_score(angle);
+            if score > best.1 {
+                best = (angle, score);
+            }
+            angle += step;
+        }
+        best.0
+    };
+
+    let max_degrees = if max_degrees.is_finite() { max_degrees.clamp(0.0, MAX_SKEW_LIMIT) } else { 0.0 };
+    let coarse = search(-max_degrees, max_degrees, 1.0);
+    search(coarse - 1.0, coarse + 1.0, 0.1)
+}
+
+/// Rotates the bitmap about its centre, filling uncovered corners with paper
+fn rotate(bitmap: &Bitmap, degrees: f64) -> Bitmap {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let cx = bitmap.width as f64 / 2.0;
+    let cy = bitmap.height as f64 / 2.0;
+
+    let mut ink = vec![false; bitmap.ink.len()];
+    for y in 0..bitmap.height {
+        for x in 0..bitmap.width {
+            let dx = x as f64 - cx;
+            let dy = y as f64 - cy;
+            let sx = (dx * cos + dy * sin + cx).round();
+            let sy = (-dx * sin + dy * cos + cy).round();
+            if sx >= 0.0 && sy >= 0.0 && (sx as usize) < bitmap.width && (sy as usize) < bitmap.height {
+                ink[y * bitmap.width + x] = bitmap.get(sx as usize, sy as usize);
+            }
+        }
+    }
+
+    Bitmap {
+        width: bitmap.width,
+        height: bitmap.height,
+        ink,
+    }
+}
+
+/// Labels 8-connected ink blobs, returning each blob's pixel indices
+fn connected_components(bitmap: &Bitmap) -> Vec<Vec<usize>> {
+    let mut visited = vec![false; bitmap.ink.len()];
+    let mut components = Vec::new();
+    let mut stack = Vec::new();
+
+    for start in 0..bitmap.ink.len() {
+        if !bitmap.ink[start] || visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        stack.push(start);
+        let mut pixels = Vec::new();
+
+        while let Some(index) = stack.pop() {
+            pixels.push(index);
+            let x = index % bitmap.width;
+            let y = index / bitmap.width;
+            for ny in y.saturating_sub(1)..=(y + 1).min(bitmap.height - 1) {
+                for nx in x.saturating_sub(1)..=(x + 1).min(bitmap.width - 1) {
+                    let neighbour = ny * bitmap.width + nx;
+                    if bitmap.ink[neighbour] && !visited[neighbour] {
+                        visited[neighbour] = true;
+                        stack.push(neighbour);
+                    }
+                }
+            }
+        }
+        components.push(pixels);
+    }
+
+    components
+}
+
+/// Drops isolated specks of ink left by sensor noise and paper texture
+fn remove_speckle(bitmap: &mut Bitmap, max_area: usize) {
+    for component in connected_components(bitmap) {
+        if component.len() <= max_area {
+            for index in component {
+                bitmap.ink[index] = false;
+            }
+        }
+    }
+}
+
+/// Clears large blobs touching the frame edge: the table, fingers and page
+/// shadows around a photographed menu rather than text on it
+fn clear_border_blobs(bitmap: &mut Bitmap) {
+    let min_extent = bitmap.width.min(bitmap.height) / 20;
+    for component in connected_components(bitmap) {
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (usize::MAX, usize::MAX, 0, 0);
+        for &index in &component {
+            let (x, y) = (index % bitmap.width, index / bitmap.width);
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+        let touches_border = min_x == 0 || min_y == 0 || max_x == bitmap.width - 1 || max_y == bitmap.height - 1;
+        let oversized = max_x - min_x > min_extent || max_y - min_y > min_extent;
+        if touches_border && oversized {
+            for index in component {
+                bitmap.ink[index] = false;
+            }
+        }
+    }
+}
+
+/// Crops to the bounding box of the remaining ink plus a margin
+fn crop_to_content(bitmap: &Bitmap, margin: usize) -> Bitmap {
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (usize::MAX, usize::MAX, 0, 0);
+    for (index, _) in bitmap.ink.iter().enumerate().filter(|(_, &ink)| ink) {
+        let (x, y) = (index % bitmap.width, index / bitmap.width);
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    if min_x == usize::MAX {
+        return Bitmap {
+            width: bitmap.width,
+            height: bitmap.height,
+            ink: bitmap.ink.clone(),
+        };
+    }
+
+    let left = min_x.saturating_sub(margin);
+    let top = min_y.saturating_sub(margin);
+    let right = (max_x + margin + 1).min(bitmap.width);
+    let bottom = (max_y + margin + 1).min(bitmap.height);
+    let width = right - left;
+
+    let mut ink = Vec::with_capacity(width * (bottom - top));
+    for y in top..bottom {
+        ink.extend_from_slice(&bitmap.ink[y * bitmap.width + left..y * bitmap.width + right]);
+    }
+
+    Bitmap {
+        width,
+        height: bottom - top,
+        ink,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bitmap(width: usize, height: usize, ink: impl Fn(usize, usize) -> bool) -> Bitmap {
+        Bitmap {
+            width,
+            height,
+            ink: (0..width * height).map(|i| ink(i % width, i / width)).collect(),
+        }
+    }
+
+    /// Rows of dashes like lines of text, 12 px tall with 12 px leading
+    fn text_rows(x: usize, y: usize) -> bool {
+        (40..360).contains(&x) && (40..360).contains(&y) && y % 24 < 12 && x % 16 < 12
+    }
+
+    fn ink_count(bitmap: &Bitmap) -> usize {
+        bitmap.ink.iter().filter(|&&ink| ink).count()
+    }
+
+    #[test]
+    fn otsu_splits_a_two_tone_image() {
+        let gray = GrayImage::from_fn(40, 40, |x, _| Luma([if x < 10 { 40 } else { 200 }]));
+        let level = otsu_threshold(&gray);
+        assert!((40..200).contains(&level), "threshold {level}");
+        let bitmap = threshold(&gray, level);
+        assert_eq!(ink_count(&bitmap), 10 * 40);
+        assert!(bitmap.get(0, 0) && !bitmap.get(39, 39));
+    }
+
+    #[test]
+    fn sauvola_finds_ink_under_uneven_lighting() {
+        // Paper brightens from 110 to 240 left to right, so ink on the right
+        // is lighter than bare paper on the left
+        let gray = GrayImage::from_fn(200, 60, |x, y| {
+            let paper = 110 + (x * 130 / 200) as u8;
+            let stroke = (20..40).contains(&y) && x % 20 < 4;
+            Luma([if stroke { paper - 100 } else { paper }])
+        });
+        let bitmap = sauvola(&gray, 0.34, 1.0 / 40.0);
+        assert!(bitmap.get(0, 30) && bitmap.get(181, 30), "strokes at both ends are ink");
+        assert!(!bitmap.get(10, 5) && !bitmap.get(190, 55), "paper at both ends is not");
+        // A single global threshold can't serve both ends of the page
+        let global = threshold(&gray, otsu_threshold(&gray));
+        assert!(global.get(10, 5) || !global.get(181, 30));
+    }
+
+    #[test]
+    fn deskew_recovers_a_known_rotation() {
+        let page = bitmap(400, 400, text_rows);
+        assert!(estimate_skew(&page, 15.0).abs() < 0.15);
+        for degrees in [4.0, -7.0] {
+            let tilted = rotate(&page, degrees);
+            let angle = estimate_skew(&tilted, 15.0);
+            assert!((angle - degrees).abs() <= 0.3, "rotated {degrees}, estimated {angle}");
+        }
+    }
+
+    #[test]
+    fn speckle_is_removed_but_text_kept() {
+        let mut page = bitmap(50, 50, |x, y| {
+            let speck = (x, y) == (3, 3) || ((40..42).contains(&x) && (5..7).contains(&y));
+            let letter = (20..30).contains(&x) && (20..30).contains(&y);
+            speck || letter
+        });
+        remove_speckle(&mut page, 6);
+        assert_eq!(ink_count(&page), 100);
+        assert!(!page.get(3, 3) && !page.get(40, 5) && page.get(25, 25));
+    }
+
+    #[test]
+    fn crop_keeps_content_plus_margin() {
+        let page = bitmap(100, 100, |x, y| (40..50).contains(&x) && (50..60).contains(&y));
+        let cropped = crop_to_content(&page, 5);
+        assert_eq!((cropped.width, cropped.height), (20, 20));
+        assert_eq!(ink_count(&cropped), 100);
+        assert!(cropped.get(5, 5) && !cropped.get(4, 4));
+
+        // Margins are clamped at the image edge, and blank pages are kept whole
+        assert_eq!(crop_to_content(&page, 80).width, 100);
+        let blank = crop_to_content(&bitmap(30, 20, |_, _| false), 5);
+        assert_eq!((blank.width, blank.height), (30, 20));
+    }
+
+    #[test]
+    fn pipeline_round_trips_a_png() {
+        let gray = GrayImage::from_fn(400, 400, |x, y| Luma([if text_rows(x as usize, y as usize) { 30 } else { 220 }]));
+        let mut png = Cursor::new(Vec::new());
+        gray.write_to(&mut png, ImageOutputFormat::Png).unwrap();
+
+        let output = preprocess(png.get_ref(), &PreprocessOptions::default()).unwrap();
+        let cleaned = image::load_from_memory(&output).unwrap().to_luma8();
+        // Cropped to the text block plus the default 16 px margin
+        assert_eq!(cleaned.dimensions(), (320 + 32, 300 + 32));
+        assert!(preprocess(b"not an image", &PreprocessOptions::default()).is_err());
+    }
+
+    #[test]
+    fn out_of_range_options_are_rejected() {
+        let valid = PreprocessOptions::default();
+        assert!(valid.validate().is_ok());
+        let cases = [
+            PreprocessOptions { max_skew_degrees: 1e20, ..valid.clone() },
+            PreprocessOptions { max_skew_degrees: f64::NAN, ..valid.clone() },
+            PreprocessOptions { max_skew_degrees: -5.0, ..valid.clone() },
+            PreprocessOptions { max_dimension: 0, ..valid.clone() },
+            PreprocessOptions { window_ratio: 0.0, ..valid.clone() },
+            PreprocessOptions { window_ratio: f64::INFINITY, ..valid.clone() },
+            PreprocessOptions { sauvola_k: -0.2, ..valid.clone() },
+            PreprocessOptions { sauvola_k: f64::NAN, ..valid.clone() },
+        ];
+        for options in cases {
+            assert!(options.validate().is_err(), "{options:?}");
+            let error = preprocess(b"not an image", &options).unwrap_err();
+            assert!(error.starts_with("Invalid options"), "{error}");
+        }
+    }
+
+    #[test]
+    fn skew_search_is_bounded() {
+        // A range this wide would never finish stepping one degree at a time
+        let page = bitmap(400, 400, text_rows);
+        assert!(estimate_skew(&page, 1e20).abs() < 0.15);
+        assert_eq!(estimate_skew(&page, f64::NAN), estimate_skew(&page, 0.0));
+    }
+}
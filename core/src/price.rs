@@ -0,0 +1,434 @@
+use serde::{Deserialize, Serialize};
+
+/// Serving a price applies to, as printed next to it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PriceLabel {
+    Glass,
+    Bottle,
+    Carafe,
+    Pint,
+    Pitcher,
+    Half,
+    Full,
+}
+
+impl PriceLabel {
+    fn parse(word: &str) -> Option<PriceLabel> {
+        let word = word.trim_end_matches(['.', ':']).to_lowercase();
+        let label = match word.as_str() {
+            "glass" | "gl" | "gls" | "btg" => PriceLabel::Glass,
+            "bottle" | "btl" | "bt" | "bot" | "bott" => PriceLabel::Bottle,
+            "carafe" | "crf" => PriceLabel::Carafe,
+            "pint" | "pt" => PriceLabel::Pint,
+            "pitcher" => PriceLabel::Pitcher,
+            "half" => PriceLabel::Half,
+            "full" => PriceLabel::Full,
+            _ => return None,
+        };
+        Some(label)
+    }
+
+    /// Label as it would be printed on a menu, e.g. "Glass"
+    pub(crate) fn display_name(self) -> &'static str {
+        match self {
+            PriceLabel::Glass => "Glass",
+            PriceLabel::Bottle => "Bottle",
+            PriceLabel::Carafe => "Carafe",
+            PriceLabel::Pint => "Pint",
+            PriceLabel::Pitcher => "Pitcher",
+            PriceLabel::Half => "Half",
+            PriceLabel::Full => "Full",
+        }
+    }
+}
+
+/// One amount in a menu line's price clause, e.g. "glass 12" or "10 oz 42"
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PricePoint {
+    pub(crate) amount: f64,
+    pub(crate) label: Option<PriceLabel>,
+    /// Pour or portion size such as "6 oz" or "750 ml"
+    pub(crate) size: Option<String>,
+}
+
+/// Every price printed at the end of a menu line
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct PriceClause {
+    pub(crate) points: Vec<PricePoint>,
+    pub(crate) market_price: bool,
+}
+
+impl PriceClause {
+    /// The first printed amount, used as the item's headline price
+    pub(crate) fn primary(&self) -> Option<f64> {
+        self.points.first().map(|p| p.amount)
+    }
+
+    /// Glass price, either labeled or the smaller of an unlabeled "12 | 46" pair
+    pub(crate) fn glass_price(&self) -> Option<f64> {
+        self.labeled(PriceLabel::Glass)
+            .or_else(|| self.unlabeled_pair().map(|(glass, _)| glass))
+    }
+
+    /// Bottle price, either labeled or the larger of an unlabeled "12 | 46" pair
+    pub(crate) fn bottle_price(&self) -> Option<f64> {
+        self.labeled(PriceLabel::Bottle)
+            .or_else(|| self.unlabeled_pair().map(|(_, bottle)| bottle))
+    }
+
+    fn labeled(&self, label: PriceLabel) -> Option<f64> {
+        self.points
+            .iter()
+            .find(|p| p.label == Some(label))
+            .map(|p| p.amount)
+    }
+
+    /// Wine lists print glass then bottle without labels
+    fn unlabeled_pair(&self) -> Option<(f64, f64)> {
+        match self.points.as_slice() {
+            [first, second]
+                if first.label.is_none()
+                    && second.label.is_none()
+                    && first.size.is_none()
+                    && second.size.is_none()
+                    && first.amount < second.amount =>
+            {
+                Some((first.amount, second.amount))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Pieces of a price clause, read right to left off the end of a line
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Amount(f64),
+    Label(PriceLabel),
+    Size(String),
+    Separator,
+    Market,
+}
+
+impl Token {
+    fn is_qualifier(&self) -> bool {
+        matches!(self, Token::Label(_) | Token::Size(_))
+    }
+}
+
+/// Splits the trailing price clause off a line, returning the remaining text
+///
+/// Understands single prices ("14", "$12.50"), glass/bottle pairs ("12 | 46",
+/// "9/34"), labeled servings ("half 14 / full 26", "12 gl / 46 btl"), sizes
+/// ("6 oz 28 / 10 oz 42") and market price ("MP", "Market Price"). Pairs
+/// without a separator ("12 46") are only read on a drinks list, where
+/// `drinks` is set; elsewhere the first number belongs to the name.
+pub(crate) fn split_trailing_prices(line: &str, drinks: bool) -> (String, Option<PriceClause>) {
+    let normalized = normalize(line);
+    let words: Vec<&str> = normalized.split_whitespace().collect();
+
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut cut = words.len();
+    while cut > 0 {
+        let word = words[cut - 1];
+        let consumed = match classify(&words[..cut]) {
+            Some((token, consumed)) => {
+                // Two bare amounts side by side on a drinks list are an
+                // unlabeled glass and bottle pair when the first is smaller
+                // ("Pinot Noir 12 46"); otherwise, or once the clause holds
+                // more, the earlier number belongs to the name, like the
+                // count in "Wings 6 12"
+                if let (Token::Amount(amount), Some(Token::Amount(next))) = (&token, tokens.last()) {
+                    if !drinks || tokens.len() > 1 || amount >= next {
+                        break;
+                    }
+                }
+                tokens.push(token);
+                consumed
+            }
+            None if word == "$" => 1,
+            None => break,
+        };
+        cut -= consumed;
+    }
+
+    // Separators at the front of the clause were leaders: "Wings - 12"
+    while matches!(tokens.last(), Some(Token::Separator)) {
+        tokens.pop();
+    }
+    tokens.reverse();
+
+    if !tokens.iter().any(|t| matches!(t, Token::Amount(_) | Token::Market)) {
+        return (line.trim().to_string(), None);
+    }
+
+    let text = trim_leader(&words[..cut].join(" ")).to_string();
+    (text, Some(build_clause(&tokens)))
+}
+
+/// Attaches labels and sizes to their amounts
+///
+/// Qualifiers printed before the first amount ("half 14") bind forward;
+/// otherwise they bind to the amount they follow ("14 half").
+fn build_clause(tokens: &[Token]) -> PriceClause {
+    let prefix = tokens.first().is_some_and(Token::is_qualifier);
+    let mut clause = PriceClause::default();
+    let mut label = None;
+    let mut size = None;
+
+    for token in tokens {
+        match token {
+            Token::Amount(amount) => {
+                if prefix {
+                    clause.points.push(PricePoint {
+                        amount: *amount,
+                        label: label.take(),
+                        size: size.take(),
+                    });
+                } else {
+                    clause.points.push(PricePoint {
+                        amount: *amount,
+                        label: None,
+                        size: None,
+                    });
+                }
+            }
+            Token::Label(l) if prefix => label = Some(*l),
+            Token::Size(s) if prefix => size = Some(s.clone()),
+            Token::Label(l) => {
+                if let Some(point) = clause.points.last_mut() {
+                    point.label.get_or_insert(*l);
+                }
+            }
+            Token::Size(s) => {
+                if let Some(point) = clause.points.last_mut() {
+                    point.size.get_or_insert_with(|| s.clone());
+                }
+            }
+            Token::Market => clause.market_price = true,
+            Token::Separator => {}
+        }
+    }
+
+    clause
+}
+
+/// Classifies the clause token ending at the last word, returning it along
+/// with the number of words it spans
+fn classify(words: &[&str]) -> Option<(Token, usize)> {
+    let word = *words.last()?;
+    let lower = word.to_lowercase();
+    let previous = words.len().checked_sub(2).map(|i| words[i]);
+
+    if matches!(word, "|" | "/" | "," | "•" | "·" | "-" | "–" | "—") {
+        return Some((Token::Separator, 1));
+    }
+    if matches!(lower.as_str(), "mp" | "m.p." | "mkt" | "market") {
+        return Some((Token::Market, 1));
+    }
+    if lower == "price" && previous.is_some_and(|p| p.eq_ignore_ascii_case("market")) {
+        return Some((Token::Market, 2));
+    }
+    if let Some(size) = parse_size(word) {
+        return Some((Token::Size(size), 1));
+    }
+    if let Some(unit) = size_unit(&lower) {
+        if let Some(quantity) = previous.and_then(parse_quantity) {
+            return Some((Token::Size(format!("{} {}", quantity, unit)), 2));
+        }
+    }
+    if let Some(label) = PriceLabel::parse(word) {
+        return Some((Token::Label(label), 1));
+    }
+    if let Some(amount) = parse_price(word) {
+        // Digits glued to a word ("B52") never reach here: they fail to parse
+        return Some((Token::Amount(amount), 1));
+    }
+    None
+}
+
+/// Parses a price token such as "12", "$12.50", "14." or "9,50"
+pub(crate) fn parse_price(token: &str) -> Option<f64> {
+    let has_currency = token.starts_with('$');
+    let token = token.trim_start_matches('$').trim_end_matches('.');
+
+    if token.is_empty() || !token.bytes().all(|b| b.is_ascii_digit() || b == b'.' || b == b',') {
+        return None;
+    }
+    if token.chars().filter(|c| matches!(c, '.' | ',')).count() > 1 {
+        return None;
+    }
+
+    let (whole, fraction) = match token.split_once(['.', ',']) {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (token, None),
+    };
+    if whole.is_empty() {
+        return None;
+    }
+    if let Some(fraction) = fraction {
+        if fraction.is_empty() || fraction.len() > 2 {
+            return None;
+        }
+    }
+
+    let value: f64 = match fraction {
+        Some(fraction) => format!("{}.{}", whole, fraction).parse().ok()?,
+        None => whole.parse().ok()?,
+    };
+
+    // Bare four-digit numbers are vintages and years, not prices
+    if !has_currency && value >= 1000.0 {
+        return None;
+    }
+    Some(value)
+}
+
+/// Parses a glued size such as "6oz" or "750ml"
+fn parse_size(word: &str) -> Option<String> {
+    let lower = word.to_lowercase();
+    let split = lower.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let (quantity, unit) = lower.split_at(split);
+    let quantity = parse_quantity(quantity)?;
+    let unit = size_unit(unit)?;
+    Some(format!("{} {}", quantity, unit))
+}
+
+fn parse_quantity(word: &str) -> Option<&str> {
+    let valid = !word.is_empty()
+        && word.starts_with(|c: char| c.is_ascii_digit())
+        && word.bytes().all(|b| b.is_ascii_digit() || b == b'.');
+    valid.then_some(word)
+}
+
+fn size_unit(unit: &str) -> Option<&'static str> {
+    match unit.trim_end_matches('.') {
+        "oz" | "ounce" | "ounces" => Some("oz"),
+        "ml" => Some("ml"),
+        "cl" => Some("cl"),
+        "l" | "ltr" | "liter" | "litre" => Some("l"),
+        _ => None,
+    }
+}
+
+/// Spreads separators glued to prices and collapses leader dots so the line
+/// splits cleanly into words: "Wings.....12" -> "Wings 12", "9/34" -> "9 / 34"
+fn normalize(line: &str) -> String {
+    let mut normalized = String::with_capacity(line.len() + 8);
+    let mut previous = ' ';
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied().unwrap_or(' ');
+        match c {
+            '.' if next == '.' => {
+                while chars.peek() == Some(&'.') {
+                    chars.next();
+                }
+                normalized.push(' ');
+            }
+            '…' => normalized.push(' '),
+            '|' | '•' | '·' => {
+                normalized.push(' ');
+                normalized.push(c);
+                normalized.push(' ');
+            }
+            // Only between amounts, so "Pinot/Gris" keeps its slash
+            '/' if previous.is_ascii_digit() && (next.is_ascii_digit() || next == '$') => {
+                normalized.push_str(" / ");
+            }
+            ',' if previous.is_ascii_digit() && next.is_whitespace() => normalized.push_str(" , "),
+            _ => normalized.push(c),
+        }
+        previous = c;
+    }
+    normalized
+}
+
+/// Strips leader dashes and stray currency symbols before a price
+fn trim_leader(text: &str) -> &str {
+    text.trim_end_matches(|c: char| {
+        c.is_whitespace() || matches!(c, '.' | '…' | '·' | '_' | '-' | '–' | '—' | '$' | '|' | '/')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(line: &str) -> (String, PriceClause) {
+        let (text, clause) = split_trailing_prices(line, true);
+        (text, clause.unwrap_or_else(|| panic!("no price in {line:?}")))
+    }
+
+    fn amounts(clause: &PriceClause) -> Vec<f64> {
+        clause.points.iter().map(|p| p.amount).collect()
+    }
+
+    #[test]
+    fn unlabeled_pairs_are_glass_then_bottle() {
+        for line in ["Pinot Noir 12 | 46", "Pinot Noir 12/46", "Pinot Noir 12 46", "Pinot Noir ..... 12 / $46"] {
+            let (text, clause) = split(line);
+            assert_eq!(text, "Pinot Noir", "line {line:?}");
+            assert_eq!(clause.glass_price(), Some(12.0), "line {line:?}");
+            assert_eq!(clause.bottle_price(), Some(46.0), "line {line:?}");
+        }
+    }
+
+    #[test]
+    fn falling_or_extra_bare_numbers_stay_in_the_name() {
+        let (text, clause) = split("Wings 12 6");
+        assert_eq!((text.as_str(), amounts(&clause)), ("Wings 12", vec![6.0]));
+        assert_eq!(clause.glass_price(), None);
+
+        let (text, clause) = split("Bin 4 12 | 46");
+        assert_eq!((text.as_str(), amounts(&clause)), ("Bin 4", vec![12.0, 46.0]));
+    }
+
+    #[test]
+    fn bare_pairs_need_a_drinks_list_or_a_separator() {
+        let (text, clause) = split_trailing_prices("Wings 6 12", false);
+        let clause = clause.unwrap();
+        assert_eq!((text.as_str(), amounts(&clause)), ("Wings 6", vec![12.0]));
+        assert_eq!(clause.glass_price(), None);
+
+        let (text, clause) = split_trailing_prices("Pinot Noir 12 | 46", false);
+        assert_eq!(text, "Pinot Noir");
+        assert_eq!(clause.unwrap().bottle_price(), Some(46.0));
+    }
+
+    #[test]
+    fn labels_bind_to_their_amounts() {
+        let (text, clause) = split("House Red half 14 / full 26");
+        assert_eq!(text, "House Red");
+        let labels: Vec<Option<PriceLabel>> = clause.points.iter().map(|p| p.label).collect();
+        assert_eq!(labels, [Some(PriceLabel::Half), Some(PriceLabel::Full)]);
+        assert_eq!(amounts(&clause), [14.0, 26.0]);
+
+        let (_, clause) = split("Sancerre 12 gl / 46 btl");
+        assert_eq!((clause.glass_price(), clause.bottle_price()), (Some(12.0), Some(46.0)));
+
+        let (_, clause) = split("IPA 6 oz 5 / 16 oz 9");
+        assert_eq!(clause.points[1].size.as_deref(), Some("16 oz"));
+        assert_eq!(clause.glass_price(), None);
+    }
+
+    #[test]
+    fn market_price_has_no_amount() {
+        for line in ["Oysters MP", "Oysters Market Price", "Oysters - m.p."] {
+            let (text, clause) = split(line);
+            assert_eq!(text, "Oysters", "line {line:?}");
+            assert!(clause.market_price && clause.points.is_empty());
+            assert_eq!(clause.primary(), None);
+        }
+    }
+
+    #[test]
+    fn four_digit_numbers_need_a_currency_sign() {
+        assert_eq!(split_trailing_prices("Dom Pérignon 2012", true), ("Dom Pérignon 2012".to_string(), None));
+        let (text, clause) = split("Dom Pérignon 2012 $1200");
+        assert_eq!((text.as_str(), amounts(&clause)), ("Dom Pérignon 2012", vec![1200.0]));
+        assert_eq!(parse_price("1200"), None);
+        assert_eq!(parse_price("9,50"), Some(9.5));
+    }
+}
@@ -0,0 +1,313 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::frames::FrameSnapshot;
+
+/// Glass card background alpha, `rgba(255, 255, 255, 0.05)`
+const CARD_OPACITY: f64 = 0.05;
+
+/// Visual effect level, from everything on to a flat, static page
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityTier {
+    Minimal = 0,
+    Low = 1,
+    Medium = 2,
+    High = 3,
+}
+
+impl QualityTier {
+    fn lower(self) -> Option<QualityTier> {
+        match self {
+            QualityTier::High => Some(QualityTier::Medium),
+            QualityTier::Medium => Some(QualityTier::Low),
+            QualityTier::Low => Some(QualityTier::Minimal),
+            QualityTier::Minimal => None,
+        }
+    }
+
+    fn higher(self) -> Option<QualityTier> {
+        match self {
+            QualityTier::Minimal => Some(QualityTier::Low),
+            QualityTier::Low => Some(QualityTier::Medium),
+            QualityTier::Medium => Some(QualityTier::High),
+            QualityTier::High => None,
+        }
+    }
+
+    /// Effect settings for this tier
+    pub(crate) fn settings(self) -> QualitySettings {
+        match self {
+            QualityTier::High => QualitySettings {
+                blur_min: 3.0,
+                blur_max: 8.0,
+                parallax_layers: 3,
+                magnetic_hover: true,
+                ken_burns: true,
+                card_opacity: CARD_OPACITY,
+            },
+            QualityTier::Medium => QualitySettings {
+                blur_min: 2.0,
+                blur_max: 5.0,
+                parallax_layers: 2,
+                magnetic_hover: true,
+                ken_burns: false,
+                card_opacity: CARD_OPACITY,
+            },
+            QualityTier::Low => QualitySettings {
+                blur_min: 0.0,
+                blur_max: 2.0,
+                parallax_layers: 1,
+                magnetic_hover: false,
+                ken_burns: false,
+                card_opacity: CARD_OPACITY,
+            },
+            // No backdrop-filter at all; cards fall back to a solid tint
+            QualityTier::Minimal => QualitySettings {
+                blur_min: 0.0,
+                blur_max: 0.0,
+                parallax_layers: 0,
+                magnetic_hover: false,
+                ken_burns: false,
+                card_opacity: CARD_OPACITY,
+            },
+        }
+    }
+}
+
+/// What the renderer may draw at a given tier
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct QualitySettings {
+    /// Backdrop blur radius in px at the top of the page
+    pub(crate) blur_min: f64,
+    /// Backdrop blur radius in px once scrolled a full viewport
+    pub(crate) blur_max: f64,
+    /// Distinct parallax depths; layers beyond this move together
+    pub(crate) parallax_layers: u32,
+    pub(crate) magnetic_hover: bool,
+    pub(crate) ken_burns: bool,
+    /// Alpha of the card's white background tint
+    pub(crate) card_opacity: f64,
+}
+
+/// Frames needed in a snapshot before it is trusted
+pub(crate) const MIN_FRAMES: usize = 30;
+
+/// Slow evaluations in a row before stepping down
+const DOWNGRADE_AFTER: u32 = 2;
+
+/// Healthy evaluations in a row before stepping back up; longer than the
+/// downgrade so a device on the edge doesn't flip between tiers
+const UPGRADE_AFTER: u32 = 5;
+
+/// Ceiling for the upgrade delay after repeated failed upgrades
+const MAX_UPGRADE_AFTER: u32 = 80;
+
+/// A downgrade this many evaluations or fewer after an upgrade means the
+/// upgrade failed
+const FLAP_WINDOW: u32 = 10;
+
+/// Steps the effect tier down when frames run over budget and back up once
+/// they have had headroom for a while
+///
+/// A snapshot is slow when its p95 frame time is half again over the frame
+/// budget or more than 15% of display refreshes were dropped, and healthy
+/// when p95 stays within 15% of the budget with under 5% dropped. Anything
+/// in between resets both streaks and holds the tier. An upgrade undone
+/// within `FLAP_WINDOW` evaluations doubles the wait before the next one.
+#[derive(Clone, Debug)]
+pub(crate) struct Governor {
+    tier: QualityTier,
+    budget_ms: f64,
+    slow_streak: u32,
+    healthy_streak: u32,
+    upgrade_after: u32,
+    /// Evaluations since the tier last stepped up, until it steps down again
+    since_upgrade: Option<u32>,
+}
+
+impl Governor {
+    pub(crate) fn new(target_fps: f64, tier: QualityTier) -> Governor {
+        let target_fps = if target_fps > 0.0 { target_fps } else { 60.0 };
+        Governor {
+            tier,
+            budget_ms: 1000.0 / target_fps,
+            slow_streak: 0,
+            healthy_streak: 0,
+            upgrade_after: UPGRADE_AFTER,
+            since_upgrade: None,
+        }
+    }
+
+    pub(crate) fn tier(&self) -> QualityTier {
+        self.tier
+    }
+
+    pub(crate) fn set_tier(&mut self, tier: QualityTier) {
+        self.tier = tier;
+        self.slow_streak = 0;
+        self.healthy_streak = 0;
+        self.since_upgrade = None;
+    }
+
+    /// Feeds one snapshot; returns the new tier when it changed
+    ///
+    /// Each snapshot should cover only frames drawn since the previous one
+    /// (`FrameSampler::take_recent`): overlapping windows would judge a slow
+    /// stretch more than once, and frames drawn at an old tier say nothing
+    /// about the new one.
+    pub(crate) fn evaluate(&mut self, snapshot: &FrameSnapshot) -> Option<QualityTier> {
+        if snapshot.frame_count < MIN_FRAMES {
+            return None;
+        }
+
+        let slow = snapshot.p95_ms > self.budget_ms * 1.5 || snapshot.dropped_frame_ratio > 0.15;
+        let healthy = snapshot.p95_ms <= self.budget_ms * 1.15 && snapshot.dropped_frame_ratio < 0.05;
+
+        if let Some(count) = self.since_upgrade.as_mut() {
+            *count += 1;
+        }
+        if slow {
+            self.slow_streak += 1;
+            self.healthy_streak = 0;
+        } else if healthy {
+            self.healthy_streak += 1;
+            self.slow_streak = 0;
+        } else {
+            self.slow_streak = 0;
+            self.healthy_streak = 0;
+        }
+
+        let next = if self.slow_streak >= DOWNGRADE_AFTER {
+            let lower = self.tier.lower();
+            if lower.is_some() && self.since_upgrade.is_some_and(|count| count <= FLAP_WINDOW) {
+                self.upgrade_after = (self.upgrade_after * 2).min(MAX_UPGRADE_AFTER);
+            }
+            lower
+        } else if self.healthy_streak >= self.upgrade_after {
+            self.tier.higher()
+        } else {
+            None
+        };
+
+        if let Some(tier) = next {
+            let upgrade = tier > self.tier;
+            self.set_tier(tier);
+            if upgrade {
+                self.since_upgrade = Some(0);
+            }
+        }
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frames::{FrameSampler, DEFAULT_CAPACITY};
+
+    fn snapshot(p95_ms: f64, dropped_frame_ratio: f64) -> FrameSnapshot {
+        FrameSnapshot {
+            frame_count: 120,
+            p95_ms,
+            dropped_frame_ratio,
+            ..FrameSnapshot::default()
+        }
+    }
+
+    fn slow() -> FrameSnapshot {
+        snapshot(40.0, 0.2)
+    }
+
+    fn healthy() -> FrameSnapshot {
+        snapshot(16.0, 0.0)
+    }
+
+    /// Feeds `count` copies of `snapshot`, returning the tier changes
+    fn feed(governor: &mut Governor, snapshot: &FrameSnapshot, count: usize) -> Vec<QualityTier> {
+        (0..count).filter_map(|_| governor.evaluate(snapshot)).collect()
+    }
+
+    #[test]
+    fn two_slow_snapshots_step_down() {
+        let mut governor = Governor::new(60.0, QualityTier::High);
+        assert_eq!(governor.evaluate(&slow()), None);
+        assert_eq!(governor.evaluate(&slow()), Some(QualityTier::Medium));
+        assert_eq!(feed(&mut governor, &slow(), 6), [QualityTier::Low, QualityTier::Minimal]);
+        assert_eq!(governor.tier(), QualityTier::Minimal);
+
+        // Too few frames to trust, or a slow run broken by an in-between one
+        let mut governor = Governor::new(60.0, QualityTier::High);
+        let sparse = FrameSnapshot { frame_count: 10, ..slow() };
+        assert_eq!(feed(&mut governor, &sparse, 4), []);
+        assert_eq!(governor.evaluate(&slow()), None);
+        assert_eq!(governor.evaluate(&snapshot(20.0, 0.0)), None);
+        assert_eq!(governor.evaluate(&slow()), None);
+    }
+
+    #[test]
+    fn five_healthy_snapshots_step_up() {
+        let mut governor = Governor::new(60.0, QualityTier::Low);
+        assert_eq!(feed(&mut governor, &healthy(), 4), []);
+        assert_eq!(governor.evaluate(&healthy()), Some(QualityTier::Medium));
+        assert_eq!(feed(&mut governor, &healthy(), 10), [QualityTier::High]);
+        assert_eq!(governor.tier(), QualityTier::High);
+    }
+
+    #[test]
+    fn quick_downgrade_after_upgrade_doubles_the_wait() {
+        let mut governor = Governor::new(60.0, QualityTier::Medium);
+        assert_eq!(feed(&mut governor, &healthy(), 5), [QualityTier::High]);
+        assert_eq!(feed(&mut governor, &slow(), 2), [QualityTier::Medium]);
+        assert_eq!(governor.upgrade_after, 10);
+
+        assert_eq!(feed(&mut governor, &healthy(), 9), []);
+        assert_eq!(governor.evaluate(&healthy()), Some(QualityTier::High));
+        assert_eq!(feed(&mut governor, &slow(), 2), [QualityTier::Medium]);
+        assert_eq!(governor.upgrade_after, 20);
+
+        // The wait stops growing at the cap
+        for _ in 0..4 {
+            let wait = governor.upgrade_after as usize;
+            assert_eq!(feed(&mut governor, &healthy(), wait), [QualityTier::High]);
+            assert_eq!(feed(&mut governor, &slow(), 2), [QualityTier::Medium]);
+        }
+        assert_eq!(governor.upgrade_after, MAX_UPGRADE_AFTER);
+    }
+
+    #[test]
+    fn downgrade_long_after_upgrade_keeps_the_wait() {
+        let mut governor = Governor::new(60.0, QualityTier::Medium);
+        assert_eq!(feed(&mut governor, &healthy(), 5), [QualityTier::High]);
+        feed(&mut governor, &healthy(), FLAP_WINDOW as usize);
+        assert_eq!(feed(&mut governor, &slow(), 2), [QualityTier::Medium]);
+        assert_eq!(governor.upgrade_after, UPGRADE_AFTER);
+
+        // Nor does a downgrade with no upgrade before it
+        let mut governor = Governor::new(60.0, QualityTier::High);
+        assert_eq!(feed(&mut governor, &slow(), 2), [QualityTier::Medium]);
+        assert_eq!(governor.upgrade_after, UPGRADE_AFTER);
+    }
+
+    #[test]
+    fn one_slow_second_does_not_step_down() {
+        let mut frames = FrameSampler::new(DEFAULT_CAPACITY);
+        let mut governor = Governor::new(60.0, QualityTier::High);
+        let mut timestamp = 0.0;
+        frames.record(timestamp);
+        // Four smooth seconds, one at 30 ms a frame, then four smooth again,
+        // judged once a second
+        let seconds = [16.7, 16.7, 16.7, 16.7, 30.0, 16.7, 16.7, 16.7, 16.7];
+        for frame_ms in seconds {
+            for _ in 0..(1000.0 / frame_ms) as usize {
+                timestamp += frame_ms;
+                frames.record(timestamp);
+            }
+            if let Some(snapshot) = frames.take_recent(MIN_FRAMES) {
+                assert_eq!(governor.evaluate(&snapshot), None);
+            }
+        }
+        assert_eq!(governor.tier(), QualityTier::High);
+    }
+}
@@ -0,0 +1,408 @@
+use std::fmt;
+
+use crate::dietary;
+use crate::normalize;
+use crate::ranking::Field;
+use crate::typo;
+
+/// Filler words guests type around what they actually want
+const STOPWORDS: [&str; 10] = ["a", "an", "the", "and", "but", "with", "something", "anything", "some", "please"];
+
+/// Words that negate what follows: "no egg white", "without dairy"
+const NEGATIONS: [&str; 3] = ["no", "not", "without"];
+
+/// Whether `word` means something to the parser rather than being searched for
+pub(crate) fn is_reserved(word: &str) -> bool {
+    let lower = word.to_lowercase();
+    word == "OR" || word == "|" || STOPWORDS.contains(&lower.as_str()) || NEGATIONS.contains(&lower.as_str())
+}
+
+/// Why a query couldn't be parsed, and where
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    /// Character offset into the query where the problem starts
+    pub position: usize,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at character {})", self.message, self.position)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// Words to look for, already normalized
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Text {
+    Word(String),
+    /// Words that must appear next to each other, in order
+    Phrase(Vec<String>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TextMatch {
+    /// Field the text must appear in; any field when `None`
+    pub(crate) field: Option<Field>,
+    pub(crate) text: Text,
+    /// Quoted text isn't typo-corrected
+    pub(crate) exact: bool,
+}
+
+/// Numeric comparison from `price:<14`, `price:>=10` or `price:10-20`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Comparison {
+    Less(f64),
+    LessOrEqual(f64),
+    Greater(f64),
+    GreaterOrEqual(f64),
+    Equal(f64),
+    Between(f64, f64),
+}
+
+impl Comparison {
+    pub(crate) fn matches(self, value: f64) -> bool {
+        match self {
+            Comparison::Less(limit) => value < limit,
+            Comparison::LessOrEqual(limit) => value <= limit,
+            Comparison::Greater(limit) => value > limit,
+            Comparison::GreaterOrEqual(limit) => value >= limit,
+            Comparison::Equal(target) => (value - target).abs() < 0.005,
+            Comparison::Between(low, high) => value >= low && value <= high,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Clause {
+    /// Bare words are optional and only add to the score; quoted phrases and
+    /// field-scoped terms must match
+    Text { matcher: TextMatch, required: bool },
+    Price(Comparison),
+    Category(String),
+    Diet(String),
+    /// Entries matching the inner clause are dropped
+    Not(Box<Clause>),
+    /// At least one alternative must match
+    AnyOf(Vec<Clause>),
+}
+
+/// A parsed search query
+///
+/// Supports bare words, `"quoted phrases"`, `field:value` prefixes (name,
+/// spirit, ingredient, producer, varietal, appellation, region, vintage,
+/// course, allergen, description, keyword, category, diet), `price:<14`
+/// style comparisons, `-term` or `no term` negation and `OR` / `|` between
+/// alternatives.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Query {
+    pub(crate) clauses: Vec<Clause>,
+}
+
+/// One whitespace-separated piece of the query
+#[derive(Clone, Debug)]
+struct Lexeme<'a> {
+    position: usize,
+    negated: bool,
+    field: Option<&'a str>,
+    value: &'a str,
+    quoted: bool,
+}
+
+impl Lexeme<'_> {
+    fn is_plain(&self) -> bool {
+        !self.negated && self.field.is_none() && !self.quoted
+    }
+
+    fn is_operator(&self) -> bool {
+        self.is_plain() && (self.value == "OR" || self.value == "|")
+    }
+
+    fn is_keyword(&self, keywords: &[&str]) -> bool {
+        self.is_plain() && keywords.contains(&self.value.to_lowercase().as_str())
+    }
+}
+
+enum Item {
+    Clause(Clause),
+    Or(usize),
+}
+
+impl Query {
+    pub(crate) fn parse(query: &str) -> Result<Query, QueryError> {
+        // Curly quotes and en dashes read as their ASCII forms, one character
+        // for one so error positions still point into the original
+        let query: String = query.chars().map(normalize::fold_punctuation).collect();
+        let lexemes = lex(&query)?;
+        let mut items: Vec<Item> = Vec::new();
+        let mut i = 0;
+
+        while i < lexemes.len() {
+            let lexeme = &lexemes[i];
+            i += 1;
+
+            if lexeme.is_operator() {
+                items.push(Item::Or(lexeme.position));
+            } else if lexeme.is_keyword(&NEGATIONS) {
+                let Some(next) = lexemes.get(i).filter(|next| !next.is_operator()) else {
+                    return Err(error(format!("Nothing to exclude after \"{}\"", lexeme.value), lexeme.position));
+                };
+                if !next.is_plain() {
+                    i += 1;
+                    let clause = clause(&Lexeme { negated: false, ..next.clone() })?;
+                    items.extend(clause.map(|c| Item::Clause(Clause::Not(Box::new(c)))));
+                    continue;
+                }
+
+                // "no egg white, …" negates the run of bare words up to a
+                // comma, filler word or operator
+                let mut words = Vec::new();
+                while let Some(next) = lexemes.get(i) {
+                    if !next.is_plain() || next.is_operator() || next.is_keyword(&STOPWORDS) || next.is_keyword(&NEGATIONS) {
+                        break;
+                    }
+                    i += 1;
+                    words.extend(typo::words(next.value));
+                    if next.value.ends_with([',', ';']) {
+                        break;
+                    }
+                }
+                if let Some(text) = text(words) {
+                    let matcher = TextMatch {
+                        field: None,
+                        text,
+                        exact: true,
+                    };
+                    let negated = Clause::Not(Box::new(Clause::Text { matcher, required: true }));
+                    items.push(Item::Clause(negated));
+                } else {
+                    return Err(error(format!("Nothing to exclude after \"{}\"", lexeme.value), lexeme.position));
+                }
+            } else if lexeme.is_keyword(&STOPWORDS) {
+                continue;
+            } else if lexeme.is_plain() {
+                // Bare words split on punctuation, so "spirit-forward" is two
+                for word in typo::words(lexeme.value) {
+                    let matcher = TextMatch {
+                        field: None,
+                        text: Text::Word(word),
+                        exact: false,
+                    };
+                    items.push(Item::Clause(Clause::Text { matcher, required: false }));
+                }
+            } else {
+                let negated = lexeme.negated;
+                if let Some(clause) = clause(lexeme)? {
+                    let clause = if negated { Clause::Not(Box::new(clause)) } else { clause };
+                    items.push(Item::Clause(clause));
+                }
+            }
+        }
+
+        group_alternatives(items)
+    }
+}
+
+/// Folds `a OR b OR c` into one `AnyOf` clause
+fn group_alternatives(items: Vec<Item>) -> Result<Query, QueryError> {
+    let mut clauses: Vec<Clause> = Vec::new();
+    let mut pending_or: Option<usize> = None;
+
+    for item in items {
+        match item {
+            Item::Or(position) => {
+                if clauses.is_empty() || pending_or.is_some() {
+                    return Err(error("\"OR\" needs a term on each side".to_string(), position));
+                }
+                pending_or = Some(position);
+            }
+            Item::Clause(clause) => {
+                if pending_or.take().is_none() {
+                    clauses.push(clause);
+                    continue;
+                }
+                match clauses.pop() {
+                    Some(Clause::AnyOf(mut alternatives)) => {
+                        alternatives.push(clause);
+                        clauses.push(Clause::AnyOf(alternatives));
+                    }
+                    Some(previous) => clauses.push(Clause::AnyOf(vec![previous, clause])),
+                    None => unreachable!("an OR is only accepted after a clause"),
+                }
+            }
+        }
+    }
+
+    if let Some(position) = pending_or {
+        return Err(error("\"OR\" needs a term on each side".to_string(), position));
+    }
+    Ok(Query { clauses })
+}
+
+/// Builds the clause for a quoted, negated or field-scoped lexeme; `None` when
+/// it holds no words, e.g. `"!!"`
+fn clause(lexeme: &Lexeme) -> Result<Option<Clause>, QueryError> {
+    let Some(name) = lexeme.field else {
+        let matcher = text(typo::words(lexeme.value).collect()).map(|text| TextMatch {
+            field: None,
+            text,
+            exact: lexeme.quoted,
+        });
+        // A negated bare word is required so the negation has something to drop
+        return Ok(matcher.map(|matcher| Clause::Text {
+            matcher,
+            required: lexeme.quoted || lexeme.negated,
+        }));
+    };
+
+    let value_position = lexeme.position + usize::from(lexeme.negated) + name.chars().count() + 1;
+    if lexeme.value.trim().is_empty() {
+        return Err(error(format!("Missing value after \"{}:\"", name), lexeme.position));
+    }
+
+    let field = match name.to_lowercase().as_str() {
+        "price" => return parse_comparison(lexeme.value, value_position).map(|c| Some(Clause::Price(c))),
+        "category" => return Ok(Some(Clause::Category(normalize::fold(lexeme.value.trim())))),
+        "diet" | "dietary" => {
+            return match dietary::keyword_tag(lexeme.value) {
+                Some(tag) => Ok(Some(Clause::Diet(tag))),
+                None => Err(error(format!("Unknown dietary tag \"{}\"", lexeme.value), value_position)),
+            };
+        }
+        "name" => Field::Name,
+        "spirit" => Field::Spirit,
+        "ingredient" | "ingredients" => Field::Ingredient,
+        "producer" => Field::Producer,
+        "varietal" | "grape" => Field::Varietal,
+        "appellation" => Field::Appellation,
+        "region" => Field::Region,
+        "vintage" => Field::Vintage,
+        "course" => Field::Course,
+        "allergen" | "allergens" => Field::Allergen,
+        "description" => Field::Description,
+        "keyword" | "keywords" | "tag" => Field::Keyword,
+        _ => return Err(error(format!("Unknown field \"{}\"", name), lexeme.position)),
+    };
+
+    Ok(text(typo::words(lexeme.value).collect()).map(|text| Clause::Text {
+        matcher: TextMatch {
+            field: Some(field),
+            text,
+            exact: lexeme.quoted,
+        },
+        required: true,
+    }))
+}
+
+fn text(mut words: Vec<String>) -> Option<Text> {
+    match words.len() {
+        0 => None,
+        1 => words.pop().map(Text::Word),
+        _ => Some(Text::Phrase(words)),
+    }
+}
+
+/// Parses `<14`, `<=14`, `>10`, `>=10`, `=12`, `12`, `10-20` or `10..20`,
+/// each optionally with a `$`
+fn parse_comparison(value: &str, position: usize) -> Result<Comparison, QueryError> {
+    let invalid = || error(format!("Invalid price \"{}\"", value), position);
+    let number = |text: &str| {
+        text.trim()
+            .trim_start_matches('$')
+            .parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite() && *n >= 0.0)
+            .ok_or_else(invalid)
+    };
+
+    let value = value.trim();
+    if let Some(rest) = value.strip_prefix("<=") {
+        return number(rest).map(Comparison::LessOrEqual);
+    }
+    if let Some(rest) = value.strip_prefix(">=") {
+        return number(rest).map(Comparison::GreaterOrEqual);
+    }
+    if let Some(rest) = value.strip_prefix('<') {
+        return number(rest).map(Comparison::Less);
+    }
+    if let Some(rest) = value.strip_prefix('>') {
+        return number(rest).map(Comparison::Greater);
+    }
+    if let Some(rest) = value.strip_prefix('=') {
+        return number(rest).map(Comparison::Equal);
+    }
+    if let Some((low, high)) = value.split_once("..").or_else(|| value.split_once('-')) {
+        let (low, high) = (number(low)?, number(high)?);
+        if low > high {
+            return Err(error(format!("Price range \"{}\" is backwards", value), position));
+        }
+        return Ok(Comparison::Between(low, high));
+    }
+    number(value).map(Comparison::Equal)
+}
+
+/// Splits the query into lexemes, reading `-` negation, `field:` prefixes
+/// and quoted values
+fn lex(query: &str) -> Result<Vec<Lexeme<'_>>, QueryError> {
+    let chars: Vec<(usize, char)> = query.char_indices().collect();
+    let byte = |i: usize| chars.get(i).map_or(query.len(), |&(b, _)| b);
+    let mut lexemes = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i].1.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let position = i;
+
+        let negated = chars[i].1 == '-' && chars.get(i + 1).is_some_and(|&(_, c)| !c.is_whitespace());
+        if negated {
+            i += 1;
+        }
+
+        // A field name is letters or underscores directly followed by ':'
+        let name_end = (i..chars.len())
+            .find(|&j| !(chars[j].1.is_ascii_alphabetic() || chars[j].1 == '_'))
+            .unwrap_or(chars.len());
+        let field = (name_end > i && chars.get(name_end).is_some_and(|&(_, c)| c == ':'))
+            .then(|| &query[byte(i)..byte(name_end)]);
+        if field.is_some() {
+            i = name_end + 1;
+        }
+
+        let quoted = chars.get(i).is_some_and(|&(_, c)| c == '"');
+        let value = if quoted {
+            let start = i + 1;
+            let Some(end) = (start..chars.len()).find(|&j| chars[j].1 == '"') else {
+                return Err(error("Unclosed quote".to_string(), i));
+            };
+            i = end + 1;
+            &query[byte(start)..byte(end)]
+        } else {
+            let start = i;
+            while i < chars.len() && !chars[i].1.is_whitespace() && chars[i].1 != '"' {
+                i += 1;
+            }
+            &query[byte(start)..byte(i)]
+        };
+
+        if negated && field.is_none() && value.is_empty() {
+            return Err(error("Nothing to exclude after \"-\"".to_string(), position));
+        }
+        lexemes.push(Lexeme {
+            position,
+            negated,
+            field,
+            value,
+            quoted,
+        });
+    }
+
+    Ok(lexemes)
+}
+
+fn error(message: String, position: usize) -> QueryError {
+    QueryError { message, position }
+}
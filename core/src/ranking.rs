@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+
+/// Where in an entry a query term matched
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum Field {
+    Name,
+    Spirit,
+    Ingredient,
+    Producer,
+    Varietal,
+    Appellation,
+    Region,
+    Vintage,
+    Course,
+    Allergen,
+    Description,
+    Keyword,
+}
+
+/// How much a term occurrence in each field counts toward relevance
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldBoosts {
+    pub name: f64,
+    pub spirit: f64,
+    pub ingredient: f64,
+    pub producer: f64,
+    pub varietal: f64,
+    pub appellation: f64,
+    pub region: f64,
+    pub vintage: f64,
+    pub course: f64,
+    pub allergen: f64,
+    pub description: f64,
+    pub keyword: f64,
+}
+
+impl Default for FieldBoosts {
+    fn default() -> Self {
+        FieldBoosts {
+            name: 3.0,
+            producer: 2.4,
+            spirit: 2.0,
+            varietal: 2.0,
+            ingredient: 1.5,
+            appellation: 1.5,
+            vintage: 1.5,
+            region: 1.2,
+            course: 1.2,
+            keyword: 1.0,
+            allergen: 1.0,
+            description: 0.6,
+        }
+    }
+}
+
+impl FieldBoosts {
+    fn get(&self, field: Field) -> f64 {
+        match field {
+            Field::Name => self.name,
+            Field::Spirit => self.spirit,
+            Field::Ingredient => self.ingredient,
+            Field::Producer => self.producer,
+            Field::Varietal => self.varietal,
+            Field::Appellation => self.appellation,
+            Field::Region => self.region,
+            Field::Vintage => self.vintage,
+            Field::Course => self.course,
+            Field::Allergen => self.allergen,
+            Field::Description => self.description,
+            Field::Keyword => self.keyword,
+        }
+    }
+}
+
+/// BM25F parameters
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RankingConfig {
+    /// Term frequency saturation; lower values stop repeated terms from
+    /// piling up score sooner
+    pub k1: f64,
+    /// How strongly long fields are penalised, from 0.0 (not at all) to 1.0
+    pub b: f64,
+    pub boosts: FieldBoosts,
+}
+
+impl Default for RankingConfig {
+    fn default() -> Self {
+        RankingConfig {
+            k1: 1.2,
+            b: 0.75,
+            boosts: FieldBoosts::default(),
+        }
+    }
+}
+
+impl RankingConfig {
+    /// A field's count of one term, weighted by the field's boost and
+    /// normalised by its length against the average for that field
+    pub(crate) fn field_frequency(&self, field: Field, count: usize, length: u32, average: f64) -> f64 {
+        let normalisation = 1.0 - self.b + self.b * f64::from(length) / average.max(1.0);
+        self.boosts.get(field) * count as f64 / normalisation
+    }
+
+    /// BM25F score of one term in a document, from the sum of its weighted
+    /// field frequencies
+    ///
+    /// Fields are summed before saturating, so a term repeated across many
+    /// ingredients can't outscore one appearance in the name.
+    pub(crate) fn term_score(&self, frequency: f64, idf: f64) -> f64 {
+        if frequency <= 0.0 {
+            return 0.0;
+        }
+        idf * frequency / (self.k1 + frequency)
+    }
+}
+
+/// Inverse document frequency, never negative so very common terms still
+/// add a little
+pub(crate) fn idf(documents: usize, frequency: u32) -> f64 {
+    let frequency = f64::from(frequency);
+    (1.0 + (documents as f64 - frequency + 0.5) / (frequency + 0.5)).ln()
+}
@@ -0,0 +1,308 @@
+use serde::Serialize;
+
+use crate::dietary;
+use crate::menu::{MenuItem, MenuSection};
+use crate::price::PricePoint;
+use crate::search::SearchItem;
+
+const CONTEXT: &str = "https://schema.org";
+
+/// schema.org `Menu`, ready to embed in a `<script type="application/ld+json">`
+#[derive(Debug, Serialize)]
+pub(crate) struct MenuLd {
+    #[serde(rename = "@context")]
+    context: &'static str,
+    #[serde(rename = "@type")]
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(rename = "hasMenuSection")]
+    sections: Vec<MenuSectionLd>,
+}
+
+#[derive(Debug, Serialize)]
+struct MenuSectionLd {
+    #[serde(rename = "@type")]
+    kind: &'static str,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    name: String,
+    #[serde(rename = "hasMenuItem")]
+    items: Vec<MenuItemLd>,
+}
+
+#[derive(Debug, Serialize)]
+struct MenuItemLd {
+    #[serde(rename = "@type")]
+    kind: &'static str,
+    name: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    description: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    offers: Vec<OfferLd>,
+    #[serde(rename = "suitableForDiet", skip_serializing_if = "Vec::is_empty")]
+    suitable_for_diet: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+struct OfferLd {
+    #[serde(rename = "@type")]
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// schema.org wants a plain decimal string, without a currency symbol
+    price: String,
+    #[serde(rename = "priceCurrency")]
+    price_currency: String,
+}
+
+/// Builds a schema.org `Menu` from extracted menu sections
+///
+/// Every printed price becomes an `Offer` named after its serving ("Glass",
+/// "Bottle 750 ml"); market-price items are listed without one, since an
+/// offer needs an amount. Dietary tags that schema.org has a `RestrictedDiet`
+/// for become `suitableForDiet`; "option" tags don't, as the dish as served
+/// isn't suitable without changes.
+pub(crate) fn menu_json_ld(sections: &[MenuSection], name: Option<String>, currency: &str) -> MenuLd {
+    MenuLd {
+        context: CONTEXT,
+        kind: "Menu",
+        name,
+        sections: sections
+            .iter()
+            .filter(|section| !section.items.is_empty())
+            .map(|section| MenuSectionLd {
+                kind: "MenuSection",
+                name: section.title.clone(),
+                items: section.items.iter().map(|item| item_json_ld(item, currency)).collect(),
+            })
+            .collect(),
+    }
+}
+
+/// Builds a schema.org `Menu` from search catalog entries, one section per
+/// category in the order categories first appear
+pub(crate) fn catalog_json_ld(items: &[SearchItem], name: Option<String>, currency: &str) -> MenuLd {
+    let mut sections: Vec<MenuSection> = Vec::new();
+    for item in items {
+        let title = category_title(&item.category);
+        let index = match sections.iter().position(|s| s.title == title) {
+            Some(index) => index,
+            None => {
+                sections.push(MenuSection {
+                    title,
+                    ..MenuSection::default()
+                });
+                sections.len() - 1
+            }
+        };
+        // Catalog keywords double as dietary tags ("vegan", "gluten-free")
+        let dietary_tags = item
+            .keywords
+            .iter()
+            .filter_map(|k| dietary::keyword_tag(k))
+            .filter(|k| diet_for_tag(k).is_some())
+            .collect();
+        sections[index].items.push(MenuItem {
+            name: item.name.clone(),
+            description: item.description.clone(),
+            price: item.price.unwrap_or(0.0),
+            prices: item
+                .price
+                .map(|amount| PricePoint {
+                    amount,
+                    label: None,
+                    size: None,
+                })
+                .into_iter()
+                .collect(),
+            dietary_tags,
+            ..MenuItem::default()
+        });
+    }
+    menu_json_ld(&sections, name, currency)
+}
+
+fn item_json_ld(item: &MenuItem, currency: &str) -> MenuItemLd {
+    // Items built by hand may only carry the headline price
+    let headline = (item.prices.is_empty() && item.price > 0.0).then_some(PricePoint {
+        amount: item.price,
+        label: None,
+        size: None,
+    });
+    let offers = item
+        .prices
+        .iter()
+        .chain(headline.as_ref())
+        .filter(|_| !item.market_price)
+        .map(|point| OfferLd {
+            kind: "Offer",
+            name: offer_name(point),
+            price: format!("{:.2}", point.amount),
+            price_currency: currency.to_string(),
+        })
+        .collect();
+
+    let mut suitable_for_diet: Vec<&'static str> = Vec::new();
+    for diet in item.dietary_tags.iter().filter_map(|tag| diet_for_tag(tag)) {
+        if !suitable_for_diet.contains(&diet) {
+            suitable_for_diet.push(diet);
+        }
+    }
+
+    MenuItemLd {
+        kind: "MenuItem",
+        name: item.name.clone(),
+        description: item.description.clone(),
+        offers,
+        suitable_for_diet,
+    }
+}
+
+fn offer_name(point: &PricePoint) -> Option<String> {
+    let parts: Vec<&str> = point
+        .label
+        .map(|label| label.display_name())
+        .into_iter()
+        .chain(point.size.as_deref())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Maps a normalized dietary tag onto a schema.org `RestrictedDiet` member
+fn diet_for_tag(tag: &str) -> Option<&'static str> {
+    let diet = match tag {
+        "gluten_free" => "https://schema.org/GlutenFreeDiet",
+        "vegan" => "https://schema.org/VeganDiet",
+        "vegetarian" => "https://schema.org/VegetarianDiet",
+        // Not "dairy_free": schema.org has no dairy-free diet, and low
+        // lactose promises less than a dairy-free dish
+        "lactose_free" => "https://schema.org/LowLactoseDiet",
+        "halal" => "https://schema.org/HalalDiet",
+        "kosher" => "https://schema.org/KosherDiet",
+        "low_calorie" => "https://schema.org/LowCalorieDiet",
+        "low_fat" => "https://schema.org/LowFatDiet",
+        "low_salt" | "low_sodium" => "https://schema.org/LowSaltDiet",
+        "diabetic" => "https://schema.org/DiabeticDiet",
+        _ => return None,
+    };
+    Some(diet)
+}
+
+/// "menu_items" reads as "Menu Items"
+fn category_title(category: &str) -> String {
+    category
+        .split(['_', '-', ' '])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::menu::parse_menu_text;
+
+    fn to_json(menu: &MenuLd) -> Value {
+        serde_json::to_value(menu).unwrap()
+    }
+
+    #[test]
+    fn menu_json_ld_shape() {
+        let sections = parse_menu_text("WINE\nSancerre 2022 glass 14 / bottle 52\nOysters MP");
+        let menu = to_json(&menu_json_ld(&sections, Some("Table 1837".to_string()), "USD"));
+        assert_eq!(
+            menu,
+            json!({
+                "@context": "https://schema.org",
+                "@type": "Menu",
+                "name": "Table 1837",
+                "hasMenuSection": [{
+                    "@type": "MenuSection",
+                    "name": "WINE",
+                    "hasMenuItem": [
+                        {
+                            "@type": "MenuItem",
+                            "name": "Sancerre 2022",
+                            "offers": [
+                                { "@type": "Offer", "name": "Glass", "price": "14.00", "priceCurrency": "USD" },
+                                { "@type": "Offer", "name": "Bottle", "price": "52.00", "priceCurrency": "USD" },
+                            ],
+                        },
+                        { "@type": "MenuItem", "name": "Oysters" },
+                    ],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn diets_map_onto_restricted_diets() {
+        let item = MenuItem {
+            name: "Grain bowl".to_string(),
+            price: 16.0,
+            dietary_tags: ["vegan", "vegetarian", "gluten_free_option", "dairy_free", "lactose_free"]
+                .map(String::from)
+                .to_vec(),
+            ..MenuItem::default()
+        };
+        let ld = serde_json::to_value(item_json_ld(&item, "EUR")).unwrap();
+        assert_eq!(
+            ld["suitableForDiet"],
+            json!([
+                "https://schema.org/VeganDiet",
+                "https://schema.org/VegetarianDiet",
+                "https://schema.org/LowLactoseDiet",
+            ])
+        );
+        assert_eq!(ld["offers"], json!([{ "@type": "Offer", "price": "16.00", "priceCurrency": "EUR" }]));
+
+        assert_eq!(diet_for_tag("dairy_free"), None);
+        let dairy_free = MenuItem {
+            name: "Sorbet".to_string(),
+            dietary_tags: vec!["dairy_free".to_string()],
+            ..MenuItem::default()
+        };
+        let ld = serde_json::to_value(item_json_ld(&dairy_free, "USD")).unwrap();
+        assert!(ld.get("suitableForDiet").is_none());
+    }
+
+    #[test]
+    fn catalog_json_ld_groups_by_category() {
+        let item = |id: &str, category: &str, keywords: &[&str], price: Option<f64>| SearchItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            category: category.to_string(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            price,
+            ..SearchItem::default()
+        };
+        let items = [
+            item("Negroni", "cocktails", &["bitter"], Some(14.0)),
+            item("Falafel", "small_plates", &["Vegan", "gluten-free", "dairy-free"], Some(9.5)),
+            item("Paloma", "cocktails", &[], None),
+        ];
+        let menu = to_json(&catalog_json_ld(&items, None, "USD"));
+        assert!(menu.get("name").is_none());
+
+        let sections = menu["hasMenuSection"].as_array().unwrap();
+        let titles: Vec<&Value> = sections.iter().map(|section| &section["name"]).collect();
+        assert_eq!(titles, ["Cocktails", "Small Plates"]);
+        assert_eq!(sections[0]["hasMenuItem"][1], json!({ "@type": "MenuItem", "name": "Paloma" }));
+
+        let falafel = &sections[1]["hasMenuItem"][0];
+        assert_eq!(falafel["offers"][0]["price"], "9.50");
+        assert_eq!(
+            falafel["suitableForDiet"],
+            json!(["https://schema.org/VeganDiet", "https://schema.org/GlutenFreeDiet"])
+        );
+    }
+}
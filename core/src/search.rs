@@ -2,76 +2,405 @@ use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[wasm_bindgen]
-pub struct FuzzySearchEngine {
+use crate::dietary;
+use crate::facets::{self, FacetValues, Facets, SearchFilters};
+use crate::index::{DocRef, InvertedIndex, TopK};
+use crate::normalize;
+use crate::query::{self, Clause, Query, QueryError, Text, TextMatch};
+use crate::ranking::{Field, RankingConfig};
+use crate::suggest::{Completion, CompletionKind, CompletionTrie};
+use crate::synonyms::{Alternative, SynonymConfig, SynonymGraph};
+use crate::typo::{self, Vocabulary};
+
+/// Fuzzy search over the bar's catalog, usable from plain Rust
+#[derive(Default)]
+pub struct SearchEngine {
     index: SearchIndex,
+    ranking: RankingConfig,
+    synonyms: SynonymGraph,
 }
 
 #[derive(Default)]
 struct SearchIndex {
     cocktails: Vec<SearchItem>,
-    wines: Vec<SearchItem>,
-    menu_items: Vec<SearchItem>,
+    wines: Vec<WineItem>,
+    menu_items: Vec<MenuEntry>,
     ingredients: HashMap<String, Vec<usize>>,
+    /// Token postings across every domain
+    postings: InvertedIndex,
+    /// Filterable attributes, by postings document id
+    facets: Vec<FacetValues>,
+    /// Every word in every indexed field, for typo correction
+    vocabulary: Vocabulary,
+    /// Names, ingredients and spirits for autocomplete
+    completions: CompletionTrie,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-struct SearchItem {
-    id: String,
-    name: String,
-    category: String,
-    keywords: Vec<String>,
-    description: String,
-    spirit: Option<String>,
-    ingredients: Vec<String>,
-    price: Option<f64>,
+/// A searchable catalog entry; cocktails use this shape
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchItem {
+    pub id: String,
+    pub name: String,
+    pub category: String,
+    pub keywords: Vec<String>,
+    pub description: String,
+    pub spirit: Option<String>,
+    pub ingredients: Vec<String>,
+    pub price: Option<f64>,
 }
 
-#[wasm_bindgen]
-impl FuzzySearchEngine {
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> FuzzySearchEngine {
-        FuzzySearchEngine {
-            index: SearchIndex::default(),
+/// A bottle on the wine list
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WineItem {
+    pub id: String,
+    pub name: String,
+    pub producer: Option<String>,
+    pub varietal: Option<String>,
+    pub appellation: Option<String>,
+    pub region: Option<String>,
+    pub vintage: Option<u16>,
+    pub description: String,
+    pub keywords: Vec<String>,
+    pub glass_price: Option<f64>,
+    pub bottle_price: Option<f64>,
+}
+
+/// A dish on the food menu
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MenuEntry {
+    pub id: String,
+    pub name: String,
+    /// Course or menu section, e.g. "Starters"
+    pub course: String,
+    pub description: String,
+    pub allergens: Vec<String>,
+    pub keywords: Vec<String>,
+    pub price: Option<f64>,
+}
+
+/// Part of the catalog a hit came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchDomain {
+    Cocktail,
+    Wine,
+    MenuItem,
+}
+
+/// The matched entry, tagged with its domain
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "domain", content = "item", rename_all = "snake_case")]
+pub enum DomainItem {
+    Cocktail(SearchItem),
+    Wine(WineItem),
+    MenuItem(MenuEntry),
+}
+
+impl DomainItem {
+    pub fn domain(&self) -> SearchDomain {
+        match self {
+            DomainItem::Cocktail(_) => SearchDomain::Cocktail,
+            DomainItem::Wine(_) => SearchDomain::Wine,
+            DomainItem::MenuItem(_) => SearchDomain::MenuItem,
         }
     }
 
-    #[wasm_bindgen]
-    pub fn index_cocktails(&mut self, data: JsValue) {
-        if let Ok(cocktails) = serde_wasm_bindgen::from_value::<Vec<SearchItem>>(data) {
-            self.index.cocktails = cocktails;
-            self.build_ingredient_index();
+    pub fn id(&self) -> &str {
+        match self {
+            DomainItem::Cocktail(item) => &item.id,
+            DomainItem::Wine(item) => &item.id,
+            DomainItem::MenuItem(item) => &item.id,
         }
     }
 
-    #[wasm_bindgen]
-    pub fn search(&self, query: &str, limit: usize) -> JsValue {
-        let query = query.to_lowercase();
-        let tokens: Vec<&str> = query.split_whitespace().collect();
-        
-        let mut results = Vec::new();
-        
-        // Search cocktails
-        for item in &self.index.cocktails {
-            let score = self.calculate_relevance(&item, &tokens);
+    pub fn name(&self) -> &str {
+        match self {
+            DomainItem::Cocktail(item) => &item.name,
+            DomainItem::Wine(item) => &item.name,
+            DomainItem::MenuItem(item) => &item.name,
+        }
+    }
+}
+
+/// A search match with its relevance score, serialized as
+/// `{ domain, item, score }`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub item: DomainItem,
+    pub score: f64,
+}
+
+/// A search with structured filters, deserialized from `{ query, limit,
+/// categories, spirits, min_price, max_price, dietary, exclude_ingredients }`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchRequest {
+    /// An empty query matches every entry that passes the filters
+    pub query: String,
+    #[serde(flatten)]
+    pub filters: SearchFilters,
+    /// Most hits to return; all of them when unset
+    pub limit: Option<usize>,
+}
+
+/// Filtered hits with the facet counts a filter UI needs
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub hits: Vec<SearchHit>,
+    /// Matches before `limit` was applied
+    pub total: usize,
+    pub facets: Facets,
+    /// A respelling of the query that would find something, when it found
+    /// nothing
+    pub did_you_mean: Option<String>,
+}
+
+/// Text an entry exposes to search, field by field, and the attributes it
+/// can be filtered on
+trait Searchable {
+    fn fields(&self) -> Vec<(Field, String)>;
+    fn facet_values(&self) -> FacetValues;
+}
+
+impl Searchable for SearchItem {
+    fn fields(&self) -> Vec<(Field, String)> {
+        let mut fields = vec![(Field::Name, self.name.clone()), (Field::Description, self.description.clone())];
+        fields.extend(self.spirit.iter().map(|s| (Field::Spirit, s.clone())));
+        fields.extend(self.ingredients.iter().map(|i| (Field::Ingredient, i.clone())));
+        fields.extend(self.keywords.iter().map(|k| (Field::Keyword, k.clone())));
+        fields
+    }
+
+    fn facet_values(&self) -> FacetValues {
+        FacetValues {
+            category: facet_value(&self.category),
+            spirit: self.spirit.as_deref().and_then(facet_value),
+            prices: self.price.into_iter().collect(),
+            dietary: dietary_tags(&self.keywords),
+            contents: self.ingredients.iter().map(|i| typo::words(i).collect()).collect(),
+        }
+    }
+}
+
+impl Searchable for WineItem {
+    fn fields(&self) -> Vec<(Field, String)> {
+        let mut fields = vec![(Field::Name, self.name.clone()), (Field::Description, self.description.clone())];
+        fields.extend(self.producer.iter().map(|p| (Field::Producer, p.clone())));
+        fields.extend(self.varietal.iter().map(|v| (Field::Varietal, v.clone())));
+        fields.extend(self.appellation.iter().map(|a| (Field::Appellation, a.clone())));
+        fields.extend(self.region.iter().map(|r| (Field::Region, r.clone())));
+        fields.extend(self.vintage.iter().map(|v| (Field::Vintage, v.to_string())));
+        fields.extend(self.keywords.iter().map(|k| (Field::Keyword, k.clone())));
+        fields
+    }
+
+    fn facet_values(&self) -> FacetValues {
+        FacetValues {
+            category: Some("wine".to_string()),
+            prices: self.glass_price.into_iter().chain(self.bottle_price).collect(),
+            dietary: dietary_tags(&self.keywords),
+            ..FacetValues::default()
+        }
+    }
+}
+
+impl Searchable for MenuEntry {
+    fn fields(&self) -> Vec<(Field, String)> {
+        let mut fields = vec![
+            (Field::Name, self.name.clone()),
+            (Field::Course, self.course.clone()),
+            (Field::Description, self.description.clone()),
+        ];
+        fields.extend(self.allergens.iter().map(|a| (Field::Allergen, a.clone())));
+        fields.extend(self.keywords.iter().map(|k| (Field::Keyword, k.clone())));
+        fields
+    }
+
+    fn facet_values(&self) -> FacetValues {
+        let mut contents: Vec<Vec<String>> = self.allergens.iter().map(|a| typo::words(a).collect()).collect();
+        contents.push(typo::words(&self.description).collect());
+        FacetValues {
+            category: facet_value(&self.course),
+            prices: self.price.into_iter().collect(),
+            dietary: dietary_tags(&self.keywords),
+            contents,
+            ..FacetValues::default()
+        }
+    }
+}
+
+fn facet_value(text: &str) -> Option<String> {
+    let value = normalize::fold(text.trim());
+    (!value.is_empty()).then_some(value)
+}
+
+fn dietary_tags(keywords: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = keywords.iter().filter_map(|k| dietary::keyword_tag(k)).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+impl SearchEngine {
+    pub fn new() -> SearchEngine {
+        SearchEngine::default()
+    }
+
+    pub fn with_ranking(ranking: RankingConfig) -> SearchEngine {
+        SearchEngine {
+            ranking,
+            ..SearchEngine::default()
+        }
+    }
+
+    /// Field boosts and BM25 parameters used to score hits
+    pub fn ranking(&self) -> &RankingConfig {
+        &self.ranking
+    }
+
+    pub fn set_ranking(&mut self, ranking: RankingConfig) {
+        self.ranking = ranking;
+    }
+
+    /// Synonyms and broader terms queries are expanded with
+    pub fn synonyms(&self) -> &SynonymConfig {
+        self.synonyms.config()
+    }
+
+    pub fn set_synonyms(&mut self, synonyms: SynonymConfig) {
+        self.synonyms = SynonymGraph::new(synonyms);
+    }
+
+    /// Replaces the indexed cocktails
+    pub fn index_cocktails(&mut self, cocktails: Vec<SearchItem>) {
+        self.index.cocktails = cocktails;
+        self.build_ingredient_index();
+        self.rebuild_index();
+    }
+
+    /// Replaces the indexed wines
+    pub fn index_wines(&mut self, wines: Vec<WineItem>) {
+        self.index.wines = wines;
+        self.rebuild_index();
+    }
+
+    /// Replaces the indexed food menu items
+    pub fn index_menu_items(&mut self, menu_items: Vec<MenuEntry>) {
+        self.index.menu_items = menu_items;
+        self.rebuild_index();
+    }
+
+    /// Returns up to `limit` matches for `query` across cocktails, wines and
+    /// menu items, best first
+    ///
+    /// Hits are ranked with BM25F, so rare words like "campari" count for
+    /// more than common ones like "juice". Query words the catalog doesn't
+    /// contain are corrected against its vocabulary, so "negorni" finds the
+    /// Negroni at a slightly lower score. Terms in the synonym dictionary
+    /// also match their synonyms and narrower terms, again scoring less than
+    /// the word itself: "whiskey" finds bourbon and rye drinks, "bubbles"
+    /// finds Champagne.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let mut top = TopK::new(limit);
+        for (document, score) in self.score_query(query) {
             if score > 0.0 {
-                results.push((item.clone(), score));
+                top.push(document, score);
             }
         }
-        
-        // Sort by relevance
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        results.truncate(limit);
-        
-        let items: Vec<SearchItem> = results.into_iter().map(|(item, _)| item).collect();
-        serde_wasm_bindgen::to_value(&items).unwrap()
+        self.hits(top)
     }
 
-    #[wasm_bindgen]
-    pub fn search_by_ingredient(&self, ingredient: &str) -> JsValue {
-        let ingredient = ingredient.to_lowercase();
+    /// Like `search`, but reads `query` in the query language: `"quoted
+    /// phrases"`, `field:value` prefixes, `price:<14` comparisons, `-term` or
+    /// `no term` negation and `OR` between alternatives
+    ///
+    /// "tequila no egg white" ranks tequila drinks and drops any with egg
+    /// white; "spirit:rye price:<14" lists every rye drink under 14.
+    pub fn search_query(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>, QueryError> {
+        let query = Query::parse(query)?;
+        let mut top = TopK::new(limit);
+        for (document, score) in self.evaluate(&query) {
+            top.push(document, score);
+        }
+        Ok(self.hits(top))
+    }
+
+    /// Runs `request`'s query, in the same language as `search_query`, with
+    /// its filters applied, counting facets over the matches
+    pub fn search_filtered(&self, request: &SearchRequest) -> Result<SearchResponse, QueryError> {
+        let candidates = self.evaluate(&Query::parse(&request.query)?);
+        let facet_values = |document: u32| &self.index.facets[document as usize];
+
+        let facets = facets::count_facets(
+            &request.filters,
+            candidates.iter().map(|&(document, _)| facet_values(document)),
+        );
+        let mut top = TopK::new(request.limit.unwrap_or(usize::MAX));
+        let mut total = 0;
+        for (document, score) in candidates {
+            if request.filters.matches(facet_values(document)) {
+                total += 1;
+                top.push(document, score);
+            }
+        }
+
+        Ok(SearchResponse {
+            hits: self.hits(top),
+            total,
+            facets,
+            did_you_mean: if total == 0 { self.did_you_mean(&request.query) } else { None },
+        })
+    }
+
+    /// Up to `limit` completions of `prefix` from item names, ingredients and
+    /// spirits, best first
+    pub fn autocomplete(&self, prefix: &str, limit: usize) -> Vec<Completion> {
+        self.index.completions.complete(prefix, limit)
+    }
+
+    /// A respelling of `query` that finds something, when it finds nothing
+    /// as typed
+    ///
+    /// Each word missing from the catalog is swapped for the closest word it
+    /// has, allowing one more edit than search's own typo correction and
+    /// preferring more common words on ties. Field prefixes, quotes and
+    /// operators are left alone.
+    pub fn did_you_mean(&self, query: &str) -> Option<String> {
+        if self.search_query(query, 1).is_ok_and(|hits| !hits.is_empty()) {
+            return None;
+        }
+
+        let mut changed = false;
+        let corrected: Vec<String> = query
+            .split_whitespace()
+            .map(|piece| {
+                let word = normalize::fold(piece);
+                let correctable = word.chars().all(char::is_alphanumeric)
+                    && !query::is_reserved(piece)
+                    && !self.index.vocabulary.contains(&word);
+                match correctable.then(|| self.closest_word(&word)).flatten() {
+                    Some(closest) => {
+                        changed = true;
+                        closest
+                    }
+                    None => piece.to_string(),
+                }
+            })
+            .collect();
+
+        let corrected = corrected.join(" ");
+        (changed && self.search_query(&corrected, 1).is_ok_and(|hits| !hits.is_empty())).then_some(corrected)
+    }
+
+    /// Returns every cocktail listing `ingredient`, ignoring case and accents
+    pub fn search_by_ingredient(&self, ingredient: &str) -> Vec<SearchItem> {
+        let ingredient = normalize::fold(ingredient);
         let mut results = Vec::new();
-        
+
         if let Some(indices) = self.index.ingredients.get(&ingredient) {
             for &idx in indices {
                 if idx < self.index.cocktails.len() {
@@ -79,76 +408,460 @@ impl FuzzySearchEngine {
                 }
             }
         }
-        
-        serde_wasm_bindgen::to_value(&results).unwrap()
+
+        results
     }
 
-    fn calculate_relevance(&self, item: &SearchItem, tokens: &[&str]) -> f64 {
-        let mut score = 0.0;
-        
-        for token in tokens {
-            // Exact name match (highest weight)
-            if item.name.to_lowercase().contains(token) {
-                score += 10.0;
+    fn closest_word(&self, word: &str) -> Option<String> {
+        let index = &self.index;
+        index
+            .vocabulary
+            .within(word, typo::max_edits(word) + 1)
+            .into_iter()
+            .min_by(|a, b| {
+                a.1.cmp(&b.1)
+                    .then_with(|| index.postings.document_frequency(b.0).cmp(&index.postings.document_frequency(a.0)))
+                    .then_with(|| a.0.cmp(b.0))
+            })
+            .map(|(closest, _)| closest.to_string())
+    }
+
+    /// Sums each query token's BM25F score per document
+    fn score_query(&self, query: &str) -> HashMap<u32, f64> {
+        self.run_scores(&typo::words(query).collect::<Vec<_>>(), None)
+    }
+
+    /// Sums the scores of consecutive query words per document, taking
+    /// dictionary terms like "pinot grigio" as one unit
+    fn run_scores(&self, words: &[String], field: Option<Field>) -> HashMap<u32, f64> {
+        let mut scores: HashMap<u32, f64> = HashMap::new();
+        let mut rest = words;
+        while !rest.is_empty() {
+            let (length, term_scores) = match self.synonyms.lookup(rest) {
+                Some((length, alternatives)) => (length, self.term_scores(&rest[..length], alternatives, field)),
+                None => (1, self.word_scores(&rest[0], field, false)),
+            };
+            for (document, score) in term_scores {
+                *scores.entry(document).or_default() += score;
             }
-            
-            // Spirit match
-            if let Some(spirit) = &item.spirit {
-                if spirit.to_lowercase().contains(token) {
-                    score += 7.0;
-                }
+            rest = &rest[length..];
+        }
+        scores
+    }
+
+    /// Scores of a dictionary term: its own words' summed scores, or its
+    /// best alternative's at that alternative's weight, whichever is higher
+    fn term_scores(
+        &self,
+        words: &[String],
+        alternatives: &[Alternative],
+        field: Option<Field>,
+    ) -> HashMap<u32, f64> {
+        let mut best: HashMap<u32, f64> = HashMap::new();
+        for word in words {
+            for (document, score) in self.word_scores(word, field, false) {
+                *best.entry(document).or_default() += score;
             }
-            
-            // Ingredient match
-            for ingredient in &item.ingredients {
-                if ingredient.to_lowercase().contains(token) {
-                    score += 5.0;
-                }
+        }
+        for (alternative, weight) in alternatives {
+            for (document, score) in self.phrase_scores(alternative, field) {
+                let best = best.entry(document).or_default();
+                *best = best.max(weight * score);
             }
-            
-            // Description match
-            if item.description.to_lowercase().contains(token) {
-                score += 2.0;
+        }
+        best
+    }
+
+    /// BM25F score of `word` in every document containing it, in `field` if
+    /// given
+    ///
+    /// Unless `exact`, the word counts once through whichever of its typo
+    /// expansions scores best, scaled down when only a correction matched.
+    fn word_scores(&self, word: &str, field: Option<Field>, exact: bool) -> HashMap<u32, f64> {
+        let index = &self.index;
+        let expansions = if exact {
+            vec![(word.to_string(), 1.0)]
+        } else {
+            index.vocabulary.expand(word)
+        };
+
+        let mut best: HashMap<u32, f64> = HashMap::new();
+        for (term, weight) in expansions {
+            for (document, score) in index.postings.score_term(&term, field, &self.ranking) {
+                let best = best.entry(document).or_default();
+                *best = best.max(weight * score);
             }
-            
-            // Keyword match
-            for keyword in &item.keywords {
-                if keyword.to_lowercase().contains(token) {
-                    score += 3.0;
+        }
+        best
+    }
+
+    /// Documents matching `matcher`, with the summed score of its words
+    fn text_scores(&self, matcher: &TextMatch, exact: bool) -> HashMap<u32, f64> {
+        let exact = exact || matcher.exact;
+        match &matcher.text {
+            Text::Word(word) if exact => self.word_scores(word, matcher.field, true),
+            Text::Word(word) => self.run_scores(std::slice::from_ref(word), matcher.field),
+            Text::Phrase(words) => self.phrase_scores(words, matcher.field),
+        }
+    }
+
+    /// Documents where `words` appear in order, with their summed scores
+    fn phrase_scores(&self, words: &[String], field: Option<Field>) -> HashMap<u32, f64> {
+        let documents = self.index.postings.phrase_documents(words, field);
+        let mut scores: HashMap<u32, f64> = documents.iter().map(|&document| (document, 0.0)).collect();
+        for word in words {
+            for (document, score) in self.index.postings.score_term(word, field, &self.ranking) {
+                if let Some(total) = scores.get_mut(&document) {
+                    *total += score;
+                }
+            }
+        }
+        scores
+    }
+
+    /// Every document `query` matches, with its score
+    ///
+    /// Bare words are optional, but a hit needs at least one of them unless
+    /// some other clause already requires a match. A query of only filters
+    /// and negations matches every entry that passes them, at score 0.
+    fn evaluate(&self, query: &Query) -> Vec<(u32, f64)> {
+        // Runs of bare words are scored together so multi-word dictionary
+        // terms expand as one, and plain queries rank exactly like `search`
+        let clauses: Vec<Prepared> = query
+            .clauses
+            .chunk_by(|a, b| is_bare_word(a) && is_bare_word(b))
+            .map(|run| match &run[0] {
+                clause if is_bare_word(clause) => {
+                    let words: Vec<String> = run
+                        .iter()
+                        .filter_map(|clause| match clause {
+                            Clause::Text {
+                                matcher: TextMatch { text: Text::Word(word), .. },
+                                ..
+                            } => Some(word.clone()),
+                            _ => None,
+                        })
+                        .collect();
+                    Prepared::Text {
+                        documents: self.run_scores(&words, None),
+                        required: false,
+                    }
                 }
+                clause => self.prepare(clause, false),
+            })
+            .collect();
+        let has_optional = clauses.iter().any(|c| matches!(c, Prepared::Text { required: false, .. }));
+        let has_required = clauses
+            .iter()
+            .any(|c| matches!(c, Prepared::Text { required: true, .. } | Prepared::AnyOf(_)));
+
+        // Hits can only come from documents some text matched, unless a
+        // required group could be satisfied by a filter alone
+        let text_bound = clauses.iter().any(|c| match c {
+            Prepared::Text { required, .. } => *required || !has_required,
+            Prepared::AnyOf(alternatives) => alternatives.iter().all(|a| matches!(a, Prepared::Text { .. })),
+            _ => false,
+        });
+        let candidates: Vec<u32> = if text_bound {
+            let mut documents: Vec<u32> = clauses.iter().flat_map(Prepared::matched_documents).collect();
+            documents.sort_unstable();
+            documents.dedup();
+            documents
+        } else {
+            (0..self.index.postings.document_count() as u32).collect()
+        };
+
+        candidates
+            .into_iter()
+            .filter_map(|document| {
+                let values = &self.index.facets[document as usize];
+                let mut score = 0.0;
+                let mut matched_optional = false;
+                for clause in &clauses {
+                    let (holds, clause_score) = clause.check(document, values);
+                    match clause {
+                        Prepared::Text { required: false, .. } => matched_optional |= holds,
+                        _ if !holds => return None,
+                        _ => {}
+                    }
+                    score += clause_score;
+                }
+                (matched_optional || has_required || !has_optional).then_some((document, score))
+            })
+            .collect()
+    }
+
+    fn prepare<'a>(&self, clause: &'a Clause, negated: bool) -> Prepared<'a> {
+        match clause {
+            Clause::Text { matcher, required } => Prepared::Text {
+                // Negated text isn't typo-corrected, so "-sherry" keeps cherry
+                documents: self.text_scores(matcher, negated),
+                required: *required,
+            },
+            Clause::Not(inner) => Prepared::Not(Box::new(self.prepare(inner, true))),
+            Clause::AnyOf(alternatives) => {
+                Prepared::AnyOf(alternatives.iter().map(|a| self.prepare(a, negated)).collect())
             }
+            Clause::Price(_) | Clause::Category(_) | Clause::Diet(_) => Prepared::Attribute(clause),
         }
-        
-        // Apply fuzzy matching bonus
-        score *= self.fuzzy_match_multiplier(&item.name, &tokens.join(" "));
-        
-        score
     }
 
-    fn fuzzy_match_multiplier(&self, text: &str, query: &str) -> f64 {
-        let text = text.to_lowercase();
-        let query = query.to_lowercase();
-        
-        // Levenshtein distance approximation
-        let max_len = text.len().max(query.len()) as f64;
-        let common_chars = text.chars()
-            .filter(|c| query.contains(*c))
-            .count() as f64;
-        
-        (common_chars / max_len).max(0.5)
+    fn hits(&self, top: TopK) -> Vec<SearchHit> {
+        top.into_sorted_vec()
+            .into_iter()
+            .map(|(document, score)| SearchHit {
+                item: self.item(self.index.postings.document(document)),
+                score,
+            })
+            .collect()
+    }
+
+    fn item(&self, document: DocRef) -> DomainItem {
+        let index = &self.index;
+        match document.domain {
+            SearchDomain::Cocktail => DomainItem::Cocktail(index.cocktails[document.position].clone()),
+            SearchDomain::Wine => DomainItem::Wine(index.wines[document.position].clone()),
+            SearchDomain::MenuItem => DomainItem::MenuItem(index.menu_items[document.position].clone()),
+        }
+    }
+
+    fn rebuild_index(&mut self) {
+        let index = &mut self.index;
+        let documents = domain_fields(SearchDomain::Cocktail, &index.cocktails)
+            .chain(domain_fields(SearchDomain::Wine, &index.wines))
+            .chain(domain_fields(SearchDomain::MenuItem, &index.menu_items));
+        index.postings = InvertedIndex::build(documents);
+        index.facets = facet_values(&index.cocktails)
+            .chain(facet_values(&index.wines))
+            .chain(facet_values(&index.menu_items))
+            .collect();
+        index.vocabulary = Vocabulary::new(index.postings.terms().map(str::to_string));
+
+        let names = index
+            .cocktails
+            .iter()
+            .map(|item| item.name.as_str())
+            .chain(index.wines.iter().map(|item| item.name.as_str()))
+            .chain(index.menu_items.iter().map(|item| item.name.as_str()))
+            .map(|name| (CompletionKind::Name, name));
+        let spirits = index
+            .cocktails
+            .iter()
+            .filter_map(|item| item.spirit.as_deref())
+            .map(|spirit| (CompletionKind::Spirit, spirit));
+        let ingredients = index
+            .cocktails
+            .iter()
+            .flat_map(|item| &item.ingredients)
+            .map(|ingredient| (CompletionKind::Ingredient, ingredient.as_str()));
+        index.completions = CompletionTrie::build(names.chain(spirits).chain(ingredients));
     }
 
     fn build_ingredient_index(&mut self) {
         self.index.ingredients.clear();
-        
+
         for (idx, cocktail) in self.index.cocktails.iter().enumerate() {
             for ingredient in &cocktail.ingredients {
-                let key = ingredient.to_lowercase();
+                let key = normalize::fold(ingredient);
                 self.index.ingredients
                     .entry(key)
-                    .or_insert_with(Vec::new)
+                    .or_default()
                     .push(idx);
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// An optional, unscoped, typo-corrected word
+fn is_bare_word(clause: &Clause) -> bool {
+    matches!(
+        clause,
+        Clause::Text {
+            matcher: TextMatch { field: None, text: Text::Word(_), exact: false },
+            required: false,
+        }
+    )
+}
+
+/// A query clause with its text matches looked up
+enum Prepared<'a> {
+    Text { documents: HashMap<u32, f64>, required: bool },
+    Attribute(&'a Clause),
+    Not(Box<Prepared<'a>>),
+    AnyOf(Vec<Prepared<'a>>),
+}
+
+impl Prepared<'_> {
+    /// Whether the clause holds for `document`, and what it adds to the score
+    fn check(&self, document: u32, values: &FacetValues) -> (bool, f64) {
+        match self {
+            Prepared::Text { documents, .. } => match documents.get(&document) {
+                Some(&score) => (true, score),
+                None => (false, 0.0),
+            },
+            Prepared::Attribute(clause) => {
+                let holds = match clause {
+                    Clause::Price(comparison) => values.prices.iter().any(|&price| comparison.matches(price)),
+                    Clause::Category(category) => values.category.as_ref() == Some(category),
+                    Clause::Diet(tag) => values.dietary.contains(tag),
+                    _ => false,
+                };
+                (holds, 0.0)
+            }
+            Prepared::Not(inner) => (!inner.check(document, values).0, 0.0),
+            Prepared::AnyOf(alternatives) => alternatives
+                .iter()
+                .map(|alternative| alternative.check(document, values))
+                .filter(|&(holds, _)| holds)
+                .fold((false, 0.0), |(_, total), (_, score)| (true, total + score)),
+        }
+    }
+
+    /// Documents this clause's own text matched
+    fn matched_documents(&self) -> Vec<u32> {
+        match self {
+            Prepared::Text { documents, .. } => documents.keys().copied().collect(),
+            Prepared::AnyOf(alternatives) => alternatives.iter().flat_map(Prepared::matched_documents).collect(),
+            Prepared::Attribute(_) | Prepared::Not(_) => Vec::new(),
+        }
+    }
+}
+
+fn domain_fields<T: Searchable>(
+    domain: SearchDomain,
+    items: &[T],
+) -> impl Iterator<Item = (DocRef, Vec<(Field, String)>)> + '_ {
+    items
+        .iter()
+        .enumerate()
+        .map(move |(position, item)| (DocRef { domain, position }, item.fields()))
+}
+
+fn facet_values<T: Searchable>(items: &[T]) -> impl Iterator<Item = FacetValues> + '_ {
+    items.iter().map(Searchable::facet_values)
+}
+
+/// Browser binding for `SearchEngine`
+#[wasm_bindgen]
+pub struct FuzzySearchEngine {
+    engine: SearchEngine,
+}
+
+#[wasm_bindgen]
+impl FuzzySearchEngine {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> FuzzySearchEngine {
+        FuzzySearchEngine {
+            engine: SearchEngine::new(),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn index_cocktails(&mut self, data: JsValue) -> Result<(), JsValue> {
+        let cocktails: Vec<SearchItem> = serde_wasm_bindgen::from_value(data)
+            .map_err(|e| JsValue::from_str(&format!("Invalid search items: {}", e)))?;
+        self.engine.index_cocktails(cocktails);
+        Ok(())
+    }
+
+    /// Indexes wines shaped `{ id, name, producer, varietal, appellation,
+    /// region, vintage, description, keywords, glass_price, bottle_price }`
+    #[wasm_bindgen]
+    pub fn index_wines(&mut self, data: JsValue) -> Result<(), JsValue> {
+        let wines: Vec<WineItem> = serde_wasm_bindgen::from_value(data)
+            .map_err(|e| JsValue::from_str(&format!("Invalid wines: {}", e)))?;
+        self.engine.index_wines(wines);
+        Ok(())
+    }
+
+    /// Indexes dishes shaped `{ id, name, course, description, allergens,
+    /// keywords, price }`
+    #[wasm_bindgen]
+    pub fn index_menu_items(&mut self, data: JsValue) -> Result<(), JsValue> {
+        let menu_items: Vec<MenuEntry> = serde_wasm_bindgen::from_value(data)
+            .map_err(|e| JsValue::from_str(&format!("Invalid menu items: {}", e)))?;
+        self.engine.index_menu_items(menu_items);
+        Ok(())
+    }
+
+    /// Sets BM25 parameters and field boosts from `{ k1, b, boosts: { name,
+    /// spirit, ingredient, … } }`; omitted values keep their defaults
+    #[wasm_bindgen]
+    pub fn set_ranking(&mut self, config: JsValue) -> Result<(), JsValue> {
+        let ranking: RankingConfig = serde_wasm_bindgen::from_value(config)
+            .map_err(|e| JsValue::from_str(&format!("Invalid ranking config: {}", e)))?;
+        self.engine.set_ranking(ranking);
+        Ok(())
+    }
+
+    /// Replaces the synonym dictionary with `{ synonyms: [["shiraz",
+    /// "syrah"], …], hypernyms: { whiskey: ["bourbon", "rye"], … },
+    /// synonym_weight, hypernym_weight }`; omitted values keep their defaults
+    #[wasm_bindgen]
+    pub fn set_synonyms(&mut self, config: JsValue) -> Result<(), JsValue> {
+        let synonyms: SynonymConfig = serde_wasm_bindgen::from_value(config)
+            .map_err(|e| JsValue::from_str(&format!("Invalid synonym config: {}", e)))?;
+        self.engine.set_synonyms(synonyms);
+        Ok(())
+    }
+
+    /// Returns `{ domain, item, score }` hits across every domain, best first
+    #[wasm_bindgen]
+    pub fn search(&self, query: &str, limit: usize) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.engine.search(query, limit))
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Like `search`, but reads the query language: `spirit:rye price:<14`,
+    /// `tequila no "egg white"`, `gin OR mezcal`; a malformed query is an
+    /// error naming where it went wrong
+    #[wasm_bindgen]
+    pub fn search_query(&self, query: &str, limit: usize) -> Result<JsValue, JsValue> {
+        let hits = self
+            .engine
+            .search_query(query, limit)
+            .map_err(|e| JsValue::from_str(&format!("Invalid query: {}", e)))?;
+        serde_wasm_bindgen::to_value(&hits)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Takes `{ query, limit, categories, spirits, min_price, max_price,
+    /// dietary, exclude_ingredients }` and returns `{ hits, total, facets }`
+    #[wasm_bindgen]
+    pub fn search_filtered(&self, request: JsValue) -> Result<JsValue, JsValue> {
+        let request: SearchRequest = serde_wasm_bindgen::from_value(request)
+            .map_err(|e| JsValue::from_str(&format!("Invalid search request: {}", e)))?;
+        let response = self
+            .engine
+            .search_filtered(&request)
+            .map_err(|e| JsValue::from_str(&format!("Invalid query: {}", e)))?;
+        serde_wasm_bindgen::to_value(&response)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// Returns up to `limit` `{ text, kind, count, score }` completions of
+    /// `prefix`, where `kind` is "name", "ingredient" or "spirit"
+    #[wasm_bindgen]
+    pub fn autocomplete(&self, prefix: &str, limit: usize) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.engine.autocomplete(prefix, limit))
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+
+    /// A corrected query when `query` has no hits
+    #[wasm_bindgen]
+    pub fn did_you_mean(&self, query: &str) -> Option<String> {
+        self.engine.did_you_mean(query)
+    }
+
+    #[wasm_bindgen]
+    pub fn search_by_ingredient(&self, ingredient: &str) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.engine.search_by_ingredient(ingredient))
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    }
+}
+
+impl Default for FuzzySearchEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
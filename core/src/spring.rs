@@ -0,0 +1,257 @@
+use serde::{Deserialize, Serialize};
+
+/// Longest frame gap integrated in one call; longer gaps (a backgrounded tab)
+/// would otherwise fling the card past its target
+const MAX_STEP_SECONDS: f64 = 0.064;
+
+/// Integration substep, small enough for stiff springs to stay stable
+const SUBSTEP_SECONDS: f64 = 1.0 / 240.0;
+
+/// Damped spring parameters
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct SpringConfig {
+    pub(crate) stiffness: f64,
+    pub(crate) damping: f64,
+    pub(crate) mass: f64,
+    /// Distance and speed, in degrees, below which a rotation counts as at
+    /// rest; scale uses a hundredth of it
+    pub(crate) rest_threshold: f64,
+}
+
+impl Default for SpringConfig {
+    fn default() -> Self {
+        // Close to critically damped: quick to settle without visible overshoot
+        SpringConfig {
+            stiffness: 170.0,
+            damping: 26.0,
+            mass: 1.0,
+            rest_threshold: 0.01,
+        }
+    }
+}
+
+/// One animated value pulled toward its target
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Spring {
+    pub(crate) position: f64,
+    pub(crate) velocity: f64,
+}
+
+impl Spring {
+    pub(crate) fn at(position: f64) -> Spring {
+        Spring {
+            position,
+            velocity: 0.0,
+        }
+    }
+
+    /// Advances the spring by `seconds` toward `target`; returns true once it
+    /// is within `threshold` of the target and slower than `threshold` per
+    /// second, at which point it snaps onto the target
+    pub(crate) fn step(&mut self, target: f64, seconds: f64, config: &SpringConfig, threshold: f64) -> bool {
+        let mass = config.mass.max(f64::EPSILON);
+        let mut remaining = seconds.clamp(0.0, MAX_STEP_SECONDS);
+        while remaining > 0.0 {
+            let dt = remaining.min(SUBSTEP_SECONDS);
+            // Semi-implicit Euler: velocity first, then position with the new
+            // velocity, which keeps the oscillation from gaining energy
+            let force = -config.stiffness * (self.position - target) - config.damping * self.velocity;
+            self.velocity += force / mass * dt;
+            self.position += self.velocity * dt;
+            remaining -= dt;
+        }
+
+        let settled = (self.position - target).abs() < threshold && self.velocity.abs() < threshold;
+        if settled {
+            *self = Spring::at(target);
+        }
+        settled
+    }
+}
+
+/// Transform channels a card animates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct CardPose {
+    pub(crate) rotate_x: f64,
+    pub(crate) rotate_y: f64,
+    pub(crate) scale: f64,
+    pub(crate) perspective: f64,
+}
+
+impl CardPose {
+    /// Flat, unscaled card
+    pub(crate) const REST: CardPose = CardPose {
+        rotate_x: 0.0,
+        rotate_y: 0.0,
+        scale: 1.0,
+        perspective: 1000.0,
+    };
+}
+
+/// Per-card spring state and the pose it is heading for
+#[derive(Clone, Debug)]
+pub(crate) struct CardSprings {
+    rotate_x: Spring,
+    rotate_y: Spring,
+    scale: Spring,
+    target: CardPose,
+    settled: bool,
+}
+
+impl CardSprings {
+    pub(crate) fn new(pose: CardPose) -> CardSprings {
+        CardSprings {
+            rotate_x: Spring::at(pose.rotate_x),
+            rotate_y: Spring::at(pose.rotate_y),
+            scale: Spring::at(pose.scale),
+            target: pose,
+            settled: true,
+        }
+    }
+
+    pub(crate) fn set_target(&mut self, target: CardPose) {
+        if target != self.target {
+            self.target = target;
+            self.settled = false;
+        }
+    }
+
+    /// Advances every channel; returns true once all of them are at rest
+    pub(crate) fn step(&mut self, seconds: f64, config: &SpringConfig) -> bool {
+        if self.settled {
+            return true;
+        }
+        let threshold = config.rest_threshold;
+        let x = self.rotate_x.step(self.target.rotate_x, seconds, config, threshold);
+        let y = self.rotate_y.step(self.target.rotate_y, seconds, config, threshold);
+        let scale = self.scale.step(self.target.scale, seconds, config, threshold * 0.01);
+        self.settled = x && y && scale;
+        self.settled
+    }
+
+    /// Moves every channel straight onto the target, for reduced motion
+    pub(crate) fn jump_to_target(&mut self) {
+        self.rotate_x = Spring::at(self.target.rotate_x);
+        self.rotate_y = Spring::at(self.target.rotate_y);
+        self.scale = Spring::at(self.target.scale);
+        self.settled = true;
+    }
+
+    pub(crate) fn is_settled(&self) -> bool {
+        self.settled
+    }
+
+    pub(crate) fn pose(&self) -> CardPose {
+        CardPose {
+            rotate_x: self.rotate_x.position,
+            rotate_y: self.rotate_y.position,
+            scale: self.scale.position,
+            perspective: self.target.perspective,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f64 = 1.0 / 60.0;
+
+    /// Steps `spring` toward `target` a frame at a time until it settles;
+    /// returns the frames taken and the furthest position reached
+    fn settle(spring: &mut Spring, target: f64, seconds: f64, config: &SpringConfig) -> (usize, f64) {
+        let mut furthest = spring.position;
+        for frame in 1..=600 {
+            let settled = spring.step(target, seconds, config, config.rest_threshold);
+            assert!(spring.position.is_finite() && spring.velocity.is_finite());
+            furthest = furthest.max(spring.position);
+            if settled {
+                return (frame, furthest);
+            }
+        }
+        panic!("spring never settled: {spring:?}");
+    }
+
+    #[test]
+    fn default_spring_settles_onto_the_target() {
+        let mut spring = Spring::at(0.0);
+        let (frames, furthest) = settle(&mut spring, 10.0, FRAME, &SpringConfig::default());
+        assert!(frames < 90, "took {frames} frames");
+        // Close to critical damping, so any overshoot is invisible
+        assert!(furthest < 10.05, "overshot to {furthest}");
+        assert_eq!(spring, Spring::at(10.0));
+    }
+
+    #[test]
+    fn long_gaps_are_capped_at_64_ms() {
+        let config = SpringConfig::default();
+        let mut capped = Spring::at(0.0);
+        let mut stalled = Spring::at(0.0);
+        capped.step(10.0, MAX_STEP_SECONDS, &config, 0.01);
+        stalled.step(10.0, 5.0, &config, 0.01);
+        assert_eq!(stalled, capped);
+        assert!(capped.position > 0.0 && capped.position < 10.0);
+
+        // Time running backwards doesn't move it
+        let mut still = Spring::at(3.0);
+        assert!(!still.step(10.0, -1.0, &config, 0.01));
+        assert_eq!(still, Spring::at(3.0));
+    }
+
+    #[test]
+    fn stiff_springs_stay_stable_with_large_steps() {
+        let config = SpringConfig {
+            stiffness: 5000.0,
+            damping: 20.0,
+            ..SpringConfig::default()
+        };
+        let mut spring = Spring::at(0.0);
+        let (_, furthest) = settle(&mut spring, 10.0, 1.0, &config);
+        // Underdamped, so it overshoots, but never gains energy
+        assert!(furthest > 10.0 && furthest < 20.0, "reached {furthest}");
+        assert_eq!(spring.position, 10.0);
+    }
+
+    #[test]
+    fn card_springs_rest_at_their_target() {
+        let config = SpringConfig::default();
+        let mut card = CardSprings::new(CardPose::REST);
+        assert!(card.step(FRAME, &config));
+
+        let tilted = CardPose {
+            rotate_x: -8.0,
+            rotate_y: 12.0,
+            scale: 1.05,
+            ..CardPose::REST
+        };
+        card.set_target(tilted);
+        assert!(!card.is_settled());
+        let frames = (1..=600).find(|_| card.step(FRAME, &config)).unwrap();
+        assert!(frames < 90, "took {frames} frames");
+        assert_eq!(card.pose(), tilted);
+
+        // Re-sending the same target leaves a settled card alone
+        card.set_target(tilted);
+        assert!(card.is_settled());
+    }
+
+    #[test]
+    fn jumping_skips_the_animation() {
+        let mut card = CardSprings::new(CardPose::REST);
+        let tilted = CardPose {
+            rotate_y: 12.0,
+            scale: 1.05,
+            ..CardPose::REST
+        };
+        card.set_target(tilted);
+        card.step(FRAME, &SpringConfig::default());
+        assert_ne!(card.pose(), tilted);
+
+        card.jump_to_target();
+        assert!(card.is_settled());
+        assert_eq!(card.pose(), tilted);
+        assert!(card.step(FRAME, &SpringConfig::default()));
+        assert_eq!(card.pose(), tilted);
+    }
+}
@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::normalize;
+use crate::typo;
+
+/// A completion matching from an entry's first word counts this much more
+/// than one matching a later word, so "marg" offers "Margarita" before
+/// "Spicy Margarita"
+const LEADING_MATCH_BOOST: f64 = 2.0;
+
+/// What a completion names
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionKind {
+    /// A cocktail, wine or dish name
+    Name,
+    Ingredient,
+    Spirit,
+}
+
+impl CompletionKind {
+    fn boost(self) -> f64 {
+        match self {
+            CompletionKind::Name => 2.0,
+            CompletionKind::Spirit => 1.5,
+            CompletionKind::Ingredient => 1.0,
+        }
+    }
+}
+
+/// A suggested completion of what's been typed
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Completion {
+    /// As written in the catalog, e.g. "Sweet Vermouth"
+    pub text: String,
+    pub kind: CompletionKind,
+    /// How many catalog entries it appears in
+    pub count: usize,
+    pub score: f64,
+}
+
+struct Entry {
+    text: String,
+    kind: CompletionKind,
+    count: usize,
+}
+
+#[derive(Default)]
+struct Node {
+    children: BTreeMap<char, usize>,
+    /// Entries whose key ends here, and whether the key starts at the entry's
+    /// first word
+    entries: Vec<(usize, bool)>,
+}
+
+/// Names, ingredients and spirits keyed by every word they contain, so a
+/// prefix of any word finds them
+pub(crate) struct CompletionTrie {
+    nodes: Vec<Node>,
+    entries: Vec<Entry>,
+}
+
+impl Default for CompletionTrie {
+    fn default() -> Self {
+        CompletionTrie {
+            nodes: vec![Node::default()],
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl CompletionTrie {
+    /// Builds the trie, merging phrases that normalize to the same words and
+    /// keeping the first spelling seen
+    pub(crate) fn build<'a>(phrases: impl IntoIterator<Item = (CompletionKind, &'a str)>) -> CompletionTrie {
+        let mut grouped: BTreeMap<(Vec<String>, CompletionKind), (String, usize)> = BTreeMap::new();
+        for (kind, text) in phrases {
+            let words: Vec<String> = typo::words(text).collect();
+            if words.is_empty() {
+                continue;
+            }
+            grouped.entry((words, kind)).or_insert_with(|| (text.trim().to_string(), 0)).1 += 1;
+        }
+
+        let mut trie = CompletionTrie::default();
+        for ((words, kind), (text, count)) in grouped {
+            let entry = trie.entries.len();
+            trie.entries.push(Entry { text, kind, count });
+            for start in 0..words.len() {
+                trie.insert(&words[start..].join(" "), entry, start == 0);
+            }
+        }
+        trie
+    }
+
+    fn insert(&mut self, key: &str, entry: usize, leading: bool) {
+        let mut node = 0;
+        for c in key.chars() {
+            node = match self.nodes[node].children.get(&c) {
+                Some(&child) => child,
+                None => {
+                    self.nodes.push(Node::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(c, child);
+                    child
+                }
+            };
+        }
+        self.nodes[node].entries.push((entry, leading));
+    }
+
+    /// Up to `limit` completions of `prefix`, best first
+    ///
+    /// Entries score by how many catalog entries they appear in, weighted by
+    /// kind and by whether the prefix matched their first word. The same text
+    /// under several kinds ("Aperol" as spirit and ingredient) is offered once.
+    pub(crate) fn complete(&self, prefix: &str, limit: usize) -> Vec<Completion> {
+        let key = typo::words(prefix).collect::<Vec<_>>().join(" ");
+        if key.is_empty() {
+            return Vec::new();
+        }
+        let Some(start) = key.chars().try_fold(0, |node, c| self.nodes[node].children.get(&c).copied()) else {
+            return Vec::new();
+        };
+
+        let mut best: HashMap<usize, f64> = HashMap::new();
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            stack.extend(node.children.values());
+            for &(entry, leading) in &node.entries {
+                let e = &self.entries[entry];
+                let score = e.count as f64 * e.kind.boost() * if leading { LEADING_MATCH_BOOST } else { 1.0 };
+                let best = best.entry(entry).or_default();
+                *best = best.max(score);
+            }
+        }
+
+        let mut completions: Vec<Completion> = best
+            .into_iter()
+            .map(|(entry, score)| {
+                let e = &self.entries[entry];
+                Completion {
+                    text: e.text.clone(),
+                    kind: e.kind,
+                    count: e.count,
+                    score,
+                }
+            })
+            .collect();
+        completions.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.text.len().cmp(&b.text.len()))
+                .then_with(|| a.text.cmp(&b.text))
+                .then_with(|| a.kind.cmp(&b.kind))
+        });
+
+        let mut seen: HashSet<String> = HashSet::new();
+        completions.retain(|completion| seen.insert(normalize::fold(&completion.text)));
+        completions.truncate(limit);
+        completions
+    }
+}
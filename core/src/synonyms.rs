@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::typo;
+
+/// Words the bar treats as meaning the same thing, and broader words that
+/// cover narrower ones
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SynonymConfig {
+    /// Groups of interchangeable terms, e.g. `["shiraz", "syrah"]`; a query
+    /// for any of them also matches the rest
+    pub synonyms: Vec<Vec<String>>,
+    /// Broader terms and what they cover, e.g. "whiskey" → bourbon, rye; a
+    /// query for the broader term also matches the narrower ones, but not
+    /// the other way round
+    pub hypernyms: BTreeMap<String, Vec<String>>,
+    /// Share of a full match a synonym hit is worth, from 0.0 to 1.0
+    pub synonym_weight: f64,
+    /// Share of a full match a narrower term's hit is worth, per level down
+    pub hypernym_weight: f64,
+}
+
+fn terms(terms: &[&str]) -> Vec<String> {
+    terms.iter().map(|term| term.to_string()).collect()
+}
+
+impl Default for SynonymConfig {
+    fn default() -> Self {
+        let synonyms = [
+            // Spellings and slang
+            &["whiskey", "whisky"][..],
+            &["mezcal", "mescal"],
+            &["margarita", "marg", "margs"],
+            &["gin and tonic", "gin tonic", "g&t"],
+            &["champagne", "champers"],
+            &["sparkling", "sparkling wine", "bubbles", "bubbly"],
+            &["low abv", "low alcohol", "sessionable"],
+            &["non alcoholic", "alcohol free", "zero proof", "mocktail"],
+            // Grapes known by more than one name
+            &["shiraz", "syrah"],
+            &["pinot grigio", "pinot gris"],
+            &["pinot noir", "pinot nero", "spätburgunder"],
+            &["grenache", "garnacha", "cannonau"],
+            &["mourvèdre", "monastrell", "mataro"],
+            &["zinfandel", "primitivo"],
+            &["tempranillo", "tinto fino", "tinta roriz"],
+            &["cabernet sauvignon", "cab sauv", "cab"],
+            &["sauvignon blanc", "sauv blanc", "savvy b"],
+            &["chardonnay", "chard"],
+        ];
+        let hypernyms = [
+            ("whiskey", &["bourbon", "rye", "scotch", "irish whiskey", "japanese whisky", "tennessee whiskey"][..]),
+            ("agave", &["tequila", "mezcal", "raicilla", "sotol"]),
+            ("brandy", &["cognac", "armagnac", "calvados", "pisco"]),
+            ("amaro", &["campari", "aperol", "fernet", "averna", "cynar"]),
+            ("sparkling", &["champagne", "prosecco", "cava", "crémant", "franciacorta", "lambrusco", "pet nat"]),
+            ("citrus", &["lemon", "lime", "grapefruit", "orange", "yuzu"]),
+            (
+                "red wine",
+                &[
+                    "cabernet sauvignon", "merlot", "pinot noir", "syrah", "nebbiolo", "tempranillo", "sangiovese",
+                    "malbec", "grenache", "zinfandel", "gamay",
+                ],
+            ),
+            (
+                "white wine",
+                &[
+                    "chardonnay", "sauvignon blanc", "riesling", "pinot grigio", "chenin blanc", "grüner veltliner",
+                    "albariño", "viognier",
+                ],
+            ),
+        ];
+
+        SynonymConfig {
+            synonyms: synonyms.iter().map(|group| terms(group)).collect(),
+            hypernyms: hypernyms
+                .iter()
+                .map(|(broader, narrower)| (broader.to_string(), terms(narrower)))
+                .collect(),
+            synonym_weight: 0.8,
+            hypernym_weight: 0.7,
+        }
+    }
+}
+
+/// A term a dictionary term also matches, as normalized words, with the
+/// share of a full match it is worth
+pub(crate) type Alternative = (Vec<String>, f64);
+
+/// `SynonymConfig` resolved into every term's alternatives, following
+/// synonyms and hypernyms as far as they lead
+#[derive(Clone, Debug)]
+pub(crate) struct SynonymGraph {
+    config: SynonymConfig,
+    expansions: HashMap<Vec<String>, Vec<Alternative>>,
+    /// Words in the longest term, to bound lookups
+    longest: usize,
+}
+
+impl Default for SynonymGraph {
+    fn default() -> Self {
+        SynonymGraph::new(SynonymConfig::default())
+    }
+}
+
+impl SynonymGraph {
+    /// Weights are clamped to 0.0–1.0, so an expansion never outscores the
+    /// term itself
+    pub(crate) fn new(config: SynonymConfig) -> SynonymGraph {
+        let synonym_weight = config.synonym_weight.clamp(0.0, 1.0);
+        let hypernym_weight = config.hypernym_weight.clamp(0.0, 1.0);
+        let normalize = |term: &String| -> Option<Vec<String>> {
+            let words: Vec<String> = typo::words(term).collect();
+            (!words.is_empty()).then_some(words)
+        };
+
+        let mut edges: BTreeMap<Vec<String>, Vec<Alternative>> = BTreeMap::new();
+        for group in &config.synonyms {
+            let group: Vec<Vec<String>> = group.iter().filter_map(normalize).collect();
+            for term in &group {
+                let others = group.iter().filter(|other| *other != term);
+                edges
+                    .entry(term.clone())
+                    .or_default()
+                    .extend(others.map(|other| (other.clone(), synonym_weight)));
+            }
+        }
+        for (broader, narrower) in &config.hypernyms {
+            let Some(broader) = normalize(broader) else {
+                continue;
+            };
+            edges
+                .entry(broader)
+                .or_default()
+                .extend(narrower.iter().filter_map(normalize).map(|term| (term, hypernym_weight)));
+        }
+
+        // Each term reaches others through chains of edges, worth the
+        // product of their weights; keep the best chain to each
+        let mut expansions = HashMap::new();
+        for term in edges.keys() {
+            let mut best: BTreeMap<&Vec<String>, f64> = BTreeMap::new();
+            let mut pending: Vec<(&Vec<String>, f64)> = vec![(term, 1.0)];
+            while let Some((from, weight)) = pending.pop() {
+                for (to, edge) in edges.get(from).into_iter().flatten() {
+                    let reached = weight * edge;
+                    if to != term && best.get(to).is_none_or(|&known| reached > known) {
+                        best.insert(to, reached);
+                        pending.push((to, reached));
+                    }
+                }
+            }
+            let alternatives: Vec<Alternative> = best
+                .into_iter()
+                .filter(|&(_, weight)| weight > 0.0)
+                .map(|(to, weight)| (to.clone(), weight))
+                .collect();
+            if !alternatives.is_empty() {
+                expansions.insert(term.clone(), alternatives);
+            }
+        }
+
+        SynonymGraph {
+            longest: expansions.keys().map(Vec::len).max().unwrap_or(0),
+            config,
+            expansions,
+        }
+    }
+
+    pub(crate) fn config(&self) -> &SynonymConfig {
+        &self.config
+    }
+
+    /// The longest dictionary term `words` starts with, as the number of
+    /// words it spans and its alternatives
+    pub(crate) fn lookup(&self, words: &[String]) -> Option<(usize, &[Alternative])> {
+        (1..=self.longest.min(words.len()))
+            .rev()
+            .find_map(|length| self.expansions.get(&words[..length]).map(|found| (length, found.as_slice())))
+    }
+}
@@ -0,0 +1,383 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// How a keyframe segment progresses, written as in CSS: "linear", "ease-out",
+/// "cubic-bezier(0.4, 0, 0.2, 1)", "steps(4, jump-end)"
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) enum Easing {
+    #[default]
+    Linear,
+    CubicBezier(f64, f64, f64, f64),
+    Steps(u32, StepJump),
+}
+
+/// Where the jumps of a `steps()` easing fall
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StepJump {
+    Start,
+    End,
+    None,
+    Both,
+}
+
+impl TryFrom<String> for Easing {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Easing::parse(&text)
+    }
+}
+
+impl Easing {
+    pub(crate) fn parse(text: &str) -> Result<Easing, String> {
+        let text = text.trim().to_lowercase();
+        let easing = match text.as_str() {
+            "linear" => Easing::Linear,
+            "ease" => Easing::CubicBezier(0.25, 0.1, 0.25, 1.0),
+            "ease-in" => Easing::CubicBezier(0.42, 0.0, 1.0, 1.0),
+            "ease-out" => Easing::CubicBezier(0.0, 0.0, 0.58, 1.0),
+            "ease-in-out" => Easing::CubicBezier(0.42, 0.0, 0.58, 1.0),
+            "step-start" => Easing::Steps(1, StepJump::Start),
+            "step-end" => Easing::Steps(1, StepJump::End),
+            _ => {
+                if let Some(args) = function_args(&text, "cubic-bezier") {
+                    let points = args
+                        .iter()
+                        .map(|a| a.parse::<f64>().ok().filter(|v| v.is_finite()))
+                        .collect::<Option<Vec<f64>>>();
+                    match points.as_deref() {
+                        // x coordinates outside 0..1 would make time run backwards
+                        Some(&[x1, y1, x2, y2]) if (0.0..=1.0).contains(&x1) && (0.0..=1.0).contains(&x2) => {
+                            Easing::CubicBezier(x1, y1, x2, y2)
+                        }
+                        _ => return Err(format!("Invalid easing \"{}\": expected cubic-bezier(x1, y1, x2, y2) with x in 0..1", text)),
+                    }
+                } else if let Some(args) = function_args(&text, "steps") {
+                    let count = args.first().and_then(|a| a.parse::<u32>().ok());
+                    let position = match args.get(1).copied() {
+                        None | Some("end") | Some("jump-end") => Some(StepJump::End),
+                        Some("start") | Some("jump-start") => Some(StepJump::Start),
+                        Some("jump-none") => Some(StepJump::None),
+                        Some("jump-both") => Some(StepJump::Both),
+                        Some(_) => None,
+                    };
+                    match (count, position) {
+                        (Some(count), Some(StepJump::None)) if count >= 2 => Easing::Steps(count, StepJump::None),
+                        (Some(count), Some(position)) if count >= 1 && position != StepJump::None => {
+                            Easing::Steps(count, position)
+                        }
+                        _ => return Err(format!("Invalid easing \"{}\": expected steps(count, position)", text)),
+                    }
+                } else {
+                    return Err(format!("Unknown easing \"{}\"", text));
+                }
+            }
+        };
+        Ok(easing)
+    }
+
+    /// Maps linear progress through a segment onto eased progress
+    pub(crate) fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+            Easing::Steps(count, position) => steps(count, position, t),
+        }
+    }
+}
+
+/// Splits "steps(4, jump-end)" into ["4", "jump-end"]
+fn function_args<'a>(text: &'a str, name: &str) -> Option<Vec<&'a str>> {
+    let inner = text.strip_prefix(name)?.trim_start().strip_prefix('(')?.strip_suffix(')')?;
+    Some(inner.split(',').map(str::trim).collect())
+}
+
+/// Solves the curve for x = `t` and returns its y, as browsers do: a few
+/// Newton iterations, falling back to bisection where the slope is flat
+fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64, t: f64) -> f64 {
+    let (cx, cy) = (3.0 * x1, 3.0 * y1);
+    let (bx, by) = (3.0 * (x2 - x1) - cx, 3.0 * (y2 - y1) - cy);
+    let (ax, ay) = (1.0 - cx - bx, 1.0 - cy - by);
+    let sample_x = |s: f64| ((ax * s + bx) * s + cx) * s;
+    let sample_y = |s: f64| ((ay * s + by) * s + cy) * s;
+    let slope_x = |s: f64| (3.0 * ax * s + 2.0 * bx) * s + cx;
+
+    const EPSILON: f64 = 1e-7;
+    let mut s = t;
+    for _ in 0..8 {
+        let error = sample_x(s) - t;
+        if error.abs() < EPSILON {
+            return sample_y(s);
+        }
+        let slope = slope_x(s);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+        s -= error / slope;
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    s = t;
+    while low < high {
+        let x = sample_x(s);
+        if (x - t).abs() < EPSILON {
+            break;
+        }
+        if t > x {
+            low = s;
+        } else {
+            high = s;
+        }
+        let next = (low + high) / 2.0;
+        if next == s {
+            break;
+        }
+        s = next;
+    }
+    sample_y(s)
+}
+
+/// CSS step easing, following the spec's step position rules
+fn steps(count: u32, position: StepJump, t: f64) -> f64 {
+    let count = f64::from(count);
+    let mut step = (t * count).floor();
+    if matches!(position, StepJump::Start | StepJump::Both) {
+        step += 1.0;
+    }
+    let jumps = match position {
+        StepJump::Both => count + 1.0,
+        StepJump::None => count - 1.0,
+        StepJump::Start | StepJump::End => count,
+    };
+    step.min(jumps) / jumps
+}
+
+/// A property value at a point in scroll progress
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub(crate) struct Keyframe {
+    /// Scroll progress from 0.0 to 1.0
+    pub(crate) offset: f64,
+    pub(crate) value: f64,
+    /// Easing from this keyframe to the next
+    #[serde(default)]
+    pub(crate) easing: Easing,
+}
+
+/// Named properties animated over scroll progress
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ScrollTimeline {
+    properties: BTreeMap<String, Vec<Keyframe>>,
+}
+
+impl ScrollTimeline {
+    /// Adds or replaces a property; keyframes may come in any order
+    pub(crate) fn register(&mut self, name: &str, mut keyframes: Vec<Keyframe>) -> Result<(), String> {
+        if keyframes.is_empty() {
+            return Err(format!("Property \"{}\" needs at least one keyframe", name));
+        }
+        if let Some(bad) = keyframes.iter().find(|k| !k.offset.is_finite() || !k.value.is_finite()) {
+            return Err(format!("Property \"{}\" has a non-finite keyframe at offset {}", name, bad.offset));
+        }
+        keyframes.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+        self.properties.insert(name.to_string(), keyframes);
+        Ok(())
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) {
+        self.properties.remove(name);
+    }
+
+    /// Every property's value at `progress`, held at the first and last
+    /// keyframe values outside their range
+    pub(crate) fn sample(&self, progress: f64) -> BTreeMap<String, f64> {
+        self.properties
+            .iter()
+            .map(|(name, keyframes)| (name.clone(), sample_keyframes(keyframes, progress)))
+            .collect()
+    }
+
+    /// Every property's first keyframe value, the state the page was
+    /// designed to rest in before any scrolling; used in place of `sample`
+    /// when motion is reduced, so a hero that fades out stays visible
+    pub(crate) fn resting_values(&self) -> BTreeMap<String, f64> {
+        self.properties
+            .iter()
+            .map(|(name, keyframes)| (name.clone(), keyframes[0].value))
+            .collect()
+    }
+}
+
+/// How far through `scroll_length` the page is, from 0 to 1
+///
+/// A position or length that doesn't divide to a finite number (NaN passed
+/// from JavaScript, or Infinity / Infinity) reads as the top of the page.
+pub(crate) fn scroll_progress(scroll_position: f64, scroll_length: f64) -> f64 {
+    let progress = if scroll_length > 0.0 { scroll_position / scroll_length } else { 0.0 };
+    if progress.is_finite() {
+        progress.clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+fn sample_keyframes(keyframes: &[Keyframe], progress: f64) -> f64 {
+    let first = &keyframes[0];
+    let last = &keyframes[keyframes.len() - 1];
+    if progress.is_nan() || progress <= first.offset {
+        return first.value;
+    }
+    if progress >= last.offset {
+        return last.value;
+    }
+
+    let end = keyframes.iter().position(|k| k.offset > progress).unwrap_or(keyframes.len() - 1);
+    let (from, to) = (&keyframes[end - 1], &keyframes[end]);
+    let span = to.offset - from.offset;
+    let t = if span > 0.0 { (progress - from.offset) / span } else { 1.0 };
+    from.value + (to.value - from.value) * from.easing.apply(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    fn keyframe(offset: f64, value: f64) -> Keyframe {
+        Keyframe {
+            offset,
+            value,
+            easing: Easing::Linear,
+        }
+    }
+
+    #[test]
+    fn parses_keywords_and_functions() {
+        assert_eq!(Easing::parse("ease-out"), Ok(Easing::CubicBezier(0.0, 0.0, 0.58, 1.0)));
+        assert_eq!(Easing::parse(" Cubic-Bezier( 0.4 , 0, 0.2, 1 ) "), Ok(Easing::CubicBezier(0.4, 0.0, 0.2, 1.0)));
+        // y may leave 0..1 for overshoot
+        assert_eq!(Easing::parse("cubic-bezier(0.5, -0.5, 0.5, 1.5)"), Ok(Easing::CubicBezier(0.5, -0.5, 0.5, 1.5)));
+        assert_eq!(Easing::parse("steps(4)"), Ok(Easing::Steps(4, StepJump::End)));
+        assert_eq!(Easing::parse("steps(4, start)"), Ok(Easing::Steps(4, StepJump::Start)));
+        assert_eq!(Easing::parse("steps(3, jump-both)"), Ok(Easing::Steps(3, StepJump::Both)));
+        assert_eq!(Easing::parse("step-end"), Ok(Easing::Steps(1, StepJump::End)));
+
+        let json = r#"{"offset": 0.5, "value": 1, "easing": "steps(2, jump-none)"}"#;
+        let keyframe: Keyframe = serde_json::from_str(json).unwrap();
+        assert_eq!(keyframe.easing, Easing::Steps(2, StepJump::None));
+    }
+
+    #[test]
+    fn rejects_malformed_easings() {
+        for text in [
+            "cubic-bezier(1.2, 0, 0, 1)",
+            "cubic-bezier(0, 0, 1)",
+            "cubic-bezier(0, nan, 1, 1)",
+            "steps(0)",
+            "steps(1, jump-none)",
+            "steps(2, middle)",
+            "steps(-1)",
+            "bounce",
+        ] {
+            assert!(Easing::parse(text).is_err(), "{text}");
+        }
+        assert!(serde_json::from_str::<Keyframe>(r#"{"offset": 0, "value": 1, "easing": "wobble"}"#).is_err());
+    }
+
+    #[test]
+    fn cubic_bezier_hits_its_endpoints() {
+        for easing in ["ease", "ease-in", "ease-out", "ease-in-out", "cubic-bezier(0.5, -0.5, 0.5, 1.5)"] {
+            let easing = Easing::parse(easing).unwrap();
+            assert!(close(easing.apply(0.0), 0.0) && close(easing.apply(1.0), 1.0), "{easing:?}");
+            // Progress outside the segment is clamped
+            assert!(close(easing.apply(-0.5), 0.0) && close(easing.apply(1.5), 1.0), "{easing:?}");
+        }
+        assert!(close(Easing::parse("ease").unwrap().apply(0.5), 0.802403));
+        assert!(close(Easing::parse("ease-in-out").unwrap().apply(0.5), 0.5));
+        assert!(close(Easing::CubicBezier(0.0, 0.0, 1.0, 1.0).apply(0.3), 0.3));
+    }
+
+    #[test]
+    fn steps_jump_where_css_puts_them() {
+        let end = Easing::Steps(4, StepJump::End);
+        let values: Vec<f64> = [0.0, 0.24, 0.25, 0.99, 1.0].iter().map(|&t| end.apply(t)).collect();
+        assert_eq!(values, [0.0, 0.0, 0.25, 0.75, 1.0]);
+
+        let start = Easing::Steps(4, StepJump::Start);
+        assert_eq!((start.apply(0.0), start.apply(0.5), start.apply(1.0)), (0.25, 0.75, 1.0));
+
+        let both = Easing::Steps(3, StepJump::Both);
+        assert_eq!((both.apply(0.0), both.apply(0.5), both.apply(1.0)), (0.25, 0.5, 1.0));
+
+        let none = Easing::Steps(3, StepJump::None);
+        assert_eq!((none.apply(0.0), none.apply(0.5), none.apply(1.0)), (0.0, 0.5, 1.0));
+    }
+
+    #[test]
+    fn timeline_holds_outside_the_keyframes() {
+        let mut timeline = ScrollTimeline::default();
+        timeline.register("opacity", vec![keyframe(0.8, 1.0), keyframe(0.2, 0.0)]).unwrap();
+        timeline.register("blur", vec![keyframe(0.5, 4.0)]).unwrap();
+
+        let opacity = |progress: f64| timeline.sample(progress)["opacity"];
+        assert_eq!((opacity(-1.0), opacity(0.1), opacity(0.2)), (0.0, 0.0, 0.0));
+        assert!(close(opacity(0.5), 0.5));
+        assert_eq!((opacity(0.8), opacity(0.9), opacity(2.0)), (1.0, 1.0, 1.0));
+        assert_eq!(timeline.sample(0.0)["blur"], 4.0);
+        assert_eq!(timeline.sample(1.0)["blur"], 4.0);
+
+        assert_eq!(timeline.resting_values(), timeline.sample(0.0));
+
+        timeline.remove("blur");
+        assert_eq!(timeline.sample(0.5).len(), 1);
+    }
+
+    #[test]
+    fn resting_values_keep_a_fade_out_visible() {
+        let mut timeline = ScrollTimeline::default();
+        timeline.register("hero-opacity", vec![keyframe(0.0, 1.0), keyframe(0.4, 0.0)]).unwrap();
+        timeline.register("header-height", vec![keyframe(0.0, 120.0), keyframe(0.2, 64.0)]).unwrap();
+        let resting = timeline.resting_values();
+        assert_eq!((resting["hero-opacity"], resting["header-height"]), (1.0, 120.0));
+    }
+
+    #[test]
+    fn non_finite_progress_reads_as_the_start() {
+        let mut timeline = ScrollTimeline::default();
+        timeline.register("blur", vec![keyframe(0.5, 4.0)]).unwrap();
+        timeline.register("opacity", vec![keyframe(0.0, 1.0), keyframe(1.0, 0.0)]).unwrap();
+        let values = timeline.sample(f64::NAN);
+        assert_eq!((values["blur"], values["opacity"]), (4.0, 1.0));
+
+        assert_eq!(scroll_progress(f64::NAN, 800.0), 0.0);
+        assert_eq!(scroll_progress(f64::INFINITY, f64::INFINITY), 0.0);
+        assert_eq!(scroll_progress(400.0, f64::NAN), 0.0);
+        assert_eq!(scroll_progress(400.0, 0.0), 0.0);
+        assert_eq!((scroll_progress(400.0, 800.0), scroll_progress(1200.0, 800.0)), (0.5, 1.0));
+    }
+
+    #[test]
+    fn eases_each_segment_from_its_start_keyframe() {
+        let mut timeline = ScrollTimeline::default();
+        let stepped = Keyframe {
+            easing: Easing::Steps(2, StepJump::End),
+            ..keyframe(0.0, 0.0)
+        };
+        timeline.register("y", vec![stepped, keyframe(0.5, 100.0), keyframe(1.0, 0.0)]).unwrap();
+        let y = |progress: f64| timeline.sample(progress)["y"];
+        assert_eq!((y(0.2), y(0.3), y(0.75)), (0.0, 50.0, 50.0));
+    }
+
+    #[test]
+    fn rejects_empty_and_non_finite_keyframes() {
+        let mut timeline = ScrollTimeline::default();
+        assert!(timeline.register("scale", Vec::new()).is_err());
+        assert!(timeline.register("scale", vec![keyframe(f64::NAN, 1.0)]).is_err());
+        assert!(timeline.register("scale", vec![keyframe(0.0, f64::INFINITY)]).is_err());
+        assert!(timeline.sample(0.5).is_empty());
+    }
+}
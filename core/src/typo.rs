@@ -0,0 +1,125 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::normalize;
+
+/// Score kept per edit a corrected token needed
+const EDIT_PENALTY: f64 = 0.2;
+
+/// Edits a token may be away from a vocabulary word and still match it;
+/// short tokens must be exact since one edit turns "gin" into "gen" or "nig"
+pub(crate) fn max_edits(token: &str) -> usize {
+    match token.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Optimal string alignment distance (Levenshtein plus adjacent
+/// transpositions), or `None` as soon as it must exceed `max`
+pub(crate) fn bounded_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    // Three rolling rows: two back for transpositions, one back, current
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        let mut row_min = current[0];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (previous[j] + 1).min(current[j - 1] + 1).min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(before[j - 2] + 1);
+            }
+            current[j] = distance;
+            row_min = row_min.min(distance);
+        }
+        // Every later row only grows from this one
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    let distance = previous[b.len()];
+    (distance <= max).then_some(distance)
+}
+
+/// Lower-cased words seen in the indexed catalog, bucketed by length so a
+/// lookup only compares words that could be within reach
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Vocabulary {
+    words: HashSet<String>,
+    by_length: HashMap<usize, Vec<String>>,
+}
+
+impl Vocabulary {
+    pub(crate) fn new(words: impl IntoIterator<Item = String>) -> Vocabulary {
+        let words: HashSet<String> = words.into_iter().collect();
+        let mut by_length: HashMap<usize, Vec<String>> = HashMap::new();
+        for word in &words {
+            by_length.entry(word.chars().count()).or_default().push(word.clone());
+        }
+        Vocabulary { words, by_length }
+    }
+
+    pub(crate) fn contains(&self, word: &str) -> bool {
+        self.words.contains(word)
+    }
+
+    /// Terms a query token should match, each with the share of a full
+    /// match it is worth
+    ///
+    /// The token itself always counts fully. Tokens missing from the
+    /// vocabulary also match every word within `max_edits`, losing a fifth
+    /// per edit.
+    pub(crate) fn expand(&self, token: &str) -> Vec<(String, f64)> {
+        let mut terms = vec![(token.to_string(), 1.0)];
+        if self.contains(token) {
+            return terms;
+        }
+
+        terms.extend(
+            self.within(token, max_edits(token))
+                .into_iter()
+                .map(|(word, distance)| (word.to_string(), 1.0 - EDIT_PENALTY * distance as f64)),
+        );
+        terms
+    }
+
+    /// Vocabulary words at most `max` edits from `token`, with their distance
+    pub(crate) fn within(&self, token: &str, max: usize) -> Vec<(&str, usize)> {
+        if max == 0 {
+            return Vec::new();
+        }
+        let length = token.chars().count();
+        let mut words = Vec::new();
+        for candidate_length in length.saturating_sub(max)..=length + max {
+            for word in self.by_length.get(&candidate_length).into_iter().flatten() {
+                if let Some(distance) = bounded_distance(token, word, max) {
+                    words.push((word.as_str(), distance));
+                }
+            }
+        }
+        words
+    }
+}
+
+/// Splits text into folded words on anything that isn't a letter or digit
+pub(crate) fn words(text: &str) -> impl Iterator<Item = String> {
+    let folded = normalize::fold(text);
+    folded
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>()
+        .into_iter()
+}
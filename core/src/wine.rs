@@ -0,0 +1,476 @@
+use serde::{Deserialize, Serialize};
+
+use crate::menu::{MenuItem, MenuSection};
+use crate::normalize;
+use crate::price::PricePoint;
+
+/// A wine list heading and the bottles under it
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct WineSection {
+    pub(crate) title: String,
+    pub(crate) wines: Vec<WineEntry>,
+}
+
+/// One wine split into the columns of the `wines` table
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct WineEntry {
+    /// Display name without the vintage, e.g. "Domaine Tempier Bandol Rosé"
+    pub(crate) name: String,
+    pub(crate) producer: Option<String>,
+    pub(crate) varietal: Option<String>,
+    pub(crate) appellation: Option<String>,
+    pub(crate) region: Option<String>,
+    pub(crate) vintage: Option<u16>,
+    /// Printed as "NV": a blend of years, common for Champagne
+    pub(crate) non_vintage: bool,
+    pub(crate) description: String,
+    pub(crate) glass_price: Option<f64>,
+    pub(crate) bottle_price: Option<f64>,
+    pub(crate) prices: Vec<PricePoint>,
+}
+
+/// Grapes and the spellings menus print them under
+const GRAPES: &[(&str, &str)] = &[
+    ("cabernet sauvignon", "Cabernet Sauvignon"),
+    ("cab sauv", "Cabernet Sauvignon"),
+    ("cabernet", "Cabernet Sauvignon"),
+    ("cab", "Cabernet Sauvignon"),
+    ("cabernet franc", "Cabernet Franc"),
+    ("cab franc", "Cabernet Franc"),
+    ("pinot noir", "Pinot Noir"),
+    ("pinot nero", "Pinot Noir"),
+    ("spatburgunder", "Pinot Noir"),
+    ("pinot grigio", "Pinot Grigio"),
+    ("pinot gris", "Pinot Grigio"),
+    ("pinot blanc", "Pinot Blanc"),
+    ("pinot meunier", "Pinot Meunier"),
+    ("merlot", "Merlot"),
+    ("malbec", "Malbec"),
+    ("syrah", "Syrah"),
+    ("shiraz", "Syrah"),
+    ("petite sirah", "Petite Sirah"),
+    ("grenache", "Grenache"),
+    ("garnacha", "Grenache"),
+    ("mourvedre", "Mourvèdre"),
+    ("monastrell", "Mourvèdre"),
+    ("tempranillo", "Tempranillo"),
+    ("sangiovese", "Sangiovese"),
+    ("nebbiolo", "Nebbiolo"),
+    ("barbera", "Barbera"),
+    ("dolcetto", "Dolcetto"),
+    ("montepulciano", "Montepulciano"),
+    ("primitivo", "Zinfandel"),
+    ("zinfandel", "Zinfandel"),
+    ("gamay", "Gamay"),
+    ("carmenere", "Carménère"),
+    ("pinotage", "Pinotage"),
+    ("chardonnay", "Chardonnay"),
+    ("sauvignon blanc", "Sauvignon Blanc"),
+    ("sauv blanc", "Sauvignon Blanc"),
+    ("riesling", "Riesling"),
+    ("chenin blanc", "Chenin Blanc"),
+    ("gewurztraminer", "Gewürztraminer"),
+    ("gruner veltliner", "Grüner Veltliner"),
+    ("gruner", "Grüner Veltliner"),
+    ("albarino", "Albariño"),
+    ("alvarinho", "Albariño"),
+    ("verdejo", "Verdejo"),
+    ("viognier", "Viognier"),
+    ("vermentino", "Vermentino"),
+    ("moscato", "Moscato"),
+    ("muscat", "Moscato"),
+    ("torrontes", "Torrontés"),
+    ("semillon", "Sémillon"),
+    ("marsanne", "Marsanne"),
+    ("roussanne", "Roussanne"),
+    ("glera", "Glera"),
+];
+
+/// Appellations with their broader region and, where the law all but fixes
+/// it, the grape the wine is made from
+const APPELLATIONS: &[(&str, &str, &str, Option<&str>)] = &[
+    ("bandol", "Bandol", "Provence", Some("Mourvèdre")),
+    ("cotes de provence", "Côtes de Provence", "Provence", None),
+    ("chateauneuf du pape", "Châteauneuf-du-Pape", "Rhône", None),
+    ("cotes du rhone", "Côtes du Rhône", "Rhône", None),
+    ("cote rotie", "Côte-Rôtie", "Rhône", Some("Syrah")),
+    ("hermitage", "Hermitage", "Rhône", Some("Syrah")),
+    ("gigondas", "Gigondas", "Rhône", None),
+    ("chablis", "Chablis", "Burgundy", Some("Chardonnay")),
+    ("meursault", "Meursault", "Burgundy", Some("Chardonnay")),
+    ("puligny montrachet", "Puligny-Montrachet", "Burgundy", Some("Chardonnay")),
+    ("pouilly fuisse", "Pouilly-Fuissé", "Burgundy", Some("Chardonnay")),
+    ("macon", "Mâcon", "Burgundy", Some("Chardonnay")),
+    ("gevrey chambertin", "Gevrey-Chambertin", "Burgundy", Some("Pinot Noir")),
+    ("nuits saint georges", "Nuits-Saint-Georges", "Burgundy", Some("Pinot Noir")),
+    ("volnay", "Volnay", "Burgundy", Some("Pinot Noir")),
+    ("beaujolais", "Beaujolais", "Burgundy", Some("Gamay")),
+    ("morgon", "Morgon", "Burgundy", Some("Gamay")),
+    ("fleurie", "Fleurie", "Burgundy", Some("Gamay")),
+    ("sancerre", "Sancerre", "Loire", Some("Sauvignon Blanc")),
+    ("pouilly fume", "Pouilly-Fumé", "Loire", Some("Sauvignon Blanc")),
+    ("vouvray", "Vouvray", "Loire", Some("Chenin Blanc")),
+    ("muscadet", "Muscadet", "Loire", None),
+    ("chinon", "Chinon", "Loire", Some("Cabernet Franc")),
+    ("margaux", "Margaux", "Bordeaux", None),
+    ("pauillac", "Pauillac", "Bordeaux", None),
+    ("saint emilion", "Saint-Émilion", "Bordeaux", None),
+    ("pomerol", "Pomerol", "Bordeaux", None),
+    ("sauternes", "Sauternes", "Bordeaux", None),
+    ("barolo", "Barolo", "Piedmont", Some("Nebbiolo")),
+    ("barbaresco", "Barbaresco", "Piedmont", Some("Nebbiolo")),
+    ("chianti classico", "Chianti Classico", "Tuscany", Some("Sangiovese")),
+    ("chianti", "Chianti", "Tuscany", Some("Sangiovese")),
+    ("brunello di montalcino", "Brunello di Montalcino", "Tuscany", Some("Sangiovese")),
+    ("bolgheri", "Bolgheri", "Tuscany", None),
+    ("amarone", "Amarone della Valpolicella", "Veneto", None),
+    ("valpolicella", "Valpolicella", "Veneto", None),
+    ("prosecco", "Prosecco", "Veneto", Some("Glera")),
+    ("soave", "Soave", "Veneto", None),
+    ("etna", "Etna", "Sicily", None),
+    ("rioja", "Rioja", "Rioja", Some("Tempranillo")),
+    ("ribera del duero", "Ribera del Duero", "Castilla y León", Some("Tempranillo")),
+    ("rias baixas", "Rías Baixas", "Galicia", Some("Albariño")),
+    ("priorat", "Priorat", "Catalonia", None),
+    ("cava", "Cava", "Catalonia", None),
+    ("douro", "Douro", "Portugal", None),
+    ("vinho verde", "Vinho Verde", "Portugal", None),
+    ("napa valley", "Napa Valley", "California", None),
+    ("sonoma coast", "Sonoma Coast", "California", None),
+    ("russian river valley", "Russian River Valley", "California", None),
+    ("sonoma", "Sonoma", "California", None),
+    ("paso robles", "Paso Robles", "California", None),
+    ("santa rita hills", "Santa Rita Hills", "California", None),
+    ("willamette valley", "Willamette Valley", "Oregon", None),
+    ("columbia valley", "Columbia Valley", "Washington", None),
+    ("walla walla", "Walla Walla", "Washington", None),
+    ("finger lakes", "Finger Lakes", "New York", None),
+    ("mendoza", "Mendoza", "Argentina", None),
+    ("marlborough", "Marlborough", "New Zealand", None),
+    ("central otago", "Central Otago", "New Zealand", None),
+    ("barossa valley", "Barossa Valley", "South Australia", None),
+    ("barossa", "Barossa Valley", "South Australia", None),
+    ("mclaren vale", "McLaren Vale", "South Australia", None),
+    ("mosel", "Mosel", "Germany", None),
+    ("wachau", "Wachau", "Austria", None),
+];
+
+/// Broad regions printed on their own, usually after the vintage
+const REGIONS: &[(&str, &str)] = &[
+    ("provence", "Provence"),
+    ("burgundy", "Burgundy"),
+    ("bourgogne", "Burgundy"),
+    ("bordeaux", "Bordeaux"),
+    ("champagne", "Champagne"),
+    ("loire", "Loire"),
+    ("loire valley", "Loire"),
+    ("rhone", "Rhône"),
+    ("rhone valley", "Rhône"),
+    ("alsace", "Alsace"),
+    ("languedoc", "Languedoc"),
+    ("tuscany", "Tuscany"),
+    ("toscana", "Tuscany"),
+    ("piedmont", "Piedmont"),
+    ("piemonte", "Piedmont"),
+    ("veneto", "Veneto"),
+    ("sicily", "Sicily"),
+    ("sicilia", "Sicily"),
+    ("california", "California"),
+    ("oregon", "Oregon"),
+    ("washington", "Washington"),
+    ("new york", "New York"),
+    ("argentina", "Argentina"),
+    ("chile", "Chile"),
+    ("new zealand", "New Zealand"),
+    ("australia", "Australia"),
+    ("south africa", "South Africa"),
+    ("germany", "Germany"),
+    ("austria", "Austria"),
+    ("portugal", "Portugal"),
+    ("spain", "Spain"),
+    ("france", "France"),
+    ("italy", "Italy"),
+];
+
+/// Style words that belong to the wine's name, not its producer
+const STYLES: &[&str] = &[
+    "rose", "rosado", "rosato", "brut", "extra brut", "blanc de blancs", "blanc de noirs", "sec", "demi sec",
+    "reserva", "gran reserva", "crianza", "riserva", "reserve", "sparkling", "cremant",
+];
+
+/// What a run of words on the line was recognised as
+#[derive(Clone, Copy, PartialEq)]
+enum Span {
+    Grape(&'static str),
+    Appellation(usize),
+    Region(&'static str),
+    Style,
+    Vintage(Option<u16>),
+}
+
+/// Re-reads generic menu sections as a wine list
+pub(crate) fn wine_sections(sections: Vec<MenuSection>) -> Vec<WineSection> {
+    sections
+        .into_iter()
+        .map(|section| {
+            let by_the_glass = is_glass_heading(&section.title);
+            let wines = section
+                .items
+                .into_iter()
+                .map(|item| wine_entry(item, by_the_glass))
+                .collect();
+            WineSection {
+                title: section.title,
+                wines,
+            }
+        })
+        .collect()
+}
+
+fn wine_entry(item: MenuItem, by_the_glass: bool) -> WineEntry {
+    // "Domaine Tempier - Bandol Rosé 2022" was split into name and
+    // description at the dash; a vintage in that tail shows it belongs
+    // to the wine line rather than being a tasting note
+    let (line, description) = if !item.description.is_empty()
+        && item.description.len() <= 60
+        && find_vintage(&item.description)
+    {
+        (format!("{} {}", item.name, item.description), String::new())
+    } else {
+        (item.name.clone(), item.description.clone())
+    };
+
+    let mut entry = parse_wine_line(&line);
+    if entry.varietal.is_none() || entry.region.is_none() {
+        let notes = parse_wine_line(&description);
+        entry.varietal = entry.varietal.or(notes.varietal);
+        entry.appellation = entry.appellation.or(notes.appellation);
+        entry.region = entry.region.or(notes.region);
+    }
+    entry.description = description;
+
+    entry.glass_price = item.glass_price;
+    entry.bottle_price = item.bottle_price;
+    if let ([only], None, None) = (item.prices.as_slice(), item.glass_price, item.bottle_price) {
+        if only.label.is_none() {
+            // A lone price means the glass under a "By the Glass" heading
+            // and the bottle everywhere else on a wine list
+            if by_the_glass {
+                entry.glass_price = Some(only.amount);
+            } else {
+                entry.bottle_price = Some(only.amount);
+            }
+        }
+    }
+    entry.prices = item.prices;
+    entry
+}
+
+/// Splits a wine line such as "Domaine Tempier Bandol Rosé 2022 Provence"
+/// into producer, name, varietal, appellation, region and vintage
+pub(crate) fn parse_wine_line(line: &str) -> WineEntry {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let folded: Vec<String> = words.iter().map(|w| fold(w)).collect();
+
+    let mut spans: Vec<(usize, usize, Span)> = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        match recognise(&folded[i..]) {
+            Some((length, span)) => {
+                spans.push((i, i + length, span));
+                i += length;
+            }
+            None => i += 1,
+        }
+    }
+
+    let mut entry = WineEntry::default();
+    for &(_, _, span) in &spans {
+        match span {
+            Span::Grape(grape) => {
+                entry.varietal.get_or_insert(grape.to_string());
+            }
+            Span::Appellation(index) => {
+                let (_, name, region, grape) = APPELLATIONS[index];
+                entry.appellation.get_or_insert(name.to_string());
+                entry.region.get_or_insert(region.to_string());
+                if let Some(grape) = grape {
+                    entry.varietal.get_or_insert(grape.to_string());
+                }
+            }
+            Span::Region(region) => {
+                entry.region.get_or_insert(region.to_string());
+            }
+            Span::Vintage(Some(year)) => entry.vintage = Some(year),
+            Span::Vintage(None) => entry.non_vintage = true,
+            Span::Style => {}
+        }
+    }
+
+    // Leading words nothing recognised are the producer
+    let first = spans.first().map_or(words.len(), |&(start, _, _)| start);
+    if first > 0 && first < words.len() {
+        entry.producer = Some(words[..first].join(" "));
+    }
+
+    // The name drops the vintage and broad regions trailing after it, which
+    // have their own columns
+    let mut end = words.len();
+    for &(start, stop, span) in spans.iter().rev() {
+        if stop != end || !matches!(span, Span::Vintage(_) | Span::Region(_)) {
+            break;
+        }
+        end = start;
+    }
+    entry.name = words[..end]
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| {
+            !spans
+                .iter()
+                .any(|&(start, stop, span)| matches!(span, Span::Vintage(_)) && (start..stop).contains(i))
+        })
+        .map(|(_, word)| *word)
+        .collect::<Vec<_>>()
+        .join(" ");
+    if entry.name.is_empty() {
+        entry.name = line.trim().to_string();
+    }
+
+    entry
+}
+
+/// Matches the longest dictionary entry starting at the first word
+///
+/// Words are compared folded, and a folded word may itself hold a space
+/// ("Côte-Rôtie" folds to "cote rotie"), so phrases are built up word by word.
+fn recognise(words: &[String]) -> Option<(usize, Span)> {
+    let first = words.first()?;
+    if let Some(vintage) = parse_vintage(first) {
+        return Some((1, Span::Vintage(vintage)));
+    }
+
+    let mut best = None;
+    let mut phrase = String::new();
+    for (length, word) in words.iter().take(4).enumerate() {
+        if length > 0 {
+            phrase.push(' ');
+        }
+        phrase.push_str(word);
+        if let Some(span) = lookup(&phrase) {
+            best = Some((length + 1, span));
+        }
+    }
+    best
+}
+
+fn lookup(phrase: &str) -> Option<Span> {
+    if let Some(index) = APPELLATIONS.iter().position(|&(pattern, ..)| pattern == phrase) {
+        return Some(Span::Appellation(index));
+    }
+    if let Some(&(_, grape)) = GRAPES.iter().find(|&&(pattern, _)| pattern == phrase) {
+        return Some(Span::Grape(grape));
+    }
+    if let Some(&(_, region)) = REGIONS.iter().find(|&&(pattern, _)| pattern == phrase) {
+        return Some(Span::Region(region));
+    }
+    STYLES.contains(&phrase).then_some(Span::Style)
+}
+
+/// Parses "2019" as a vintage and "NV" as non-vintage
+fn parse_vintage(word: &str) -> Option<Option<u16>> {
+    if word == "nv" {
+        return Some(None);
+    }
+    let year: u16 = word.parse().ok()?;
+    (word.len() == 4 && (1900..=2099).contains(&year)).then_some(Some(year))
+}
+
+fn find_vintage(text: &str) -> bool {
+    text.split_whitespace().any(|word| matches!(parse_vintage(&fold(word)), Some(Some(_))))
+}
+
+fn is_glass_heading(title: &str) -> bool {
+    let title = title.to_lowercase();
+    title.contains("glass") || title.contains("btg") || title.contains("pour")
+}
+
+/// Folds a word with `normalize::fold` and strips its punctuation, so
+/// "Rosé", "Mâcon" and "Côte-Rôtie" match their dictionary spellings
+fn fold(word: &str) -> String {
+    normalize::fold(word)
+        .chars()
+        .map(|c| if c == '-' { ' ' } else { c })
+        .filter(|&c| c.is_alphanumeric() || c == ' ')
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::parse_menu_text;
+
+    #[test]
+    fn bandol_line_splits_into_columns() {
+        let entry = parse_wine_line("Domaine Tempier Bandol Rosé 2022 Provence");
+        assert_eq!(entry.name, "Domaine Tempier Bandol Rosé");
+        assert_eq!(entry.producer.as_deref(), Some("Domaine Tempier"));
+        assert_eq!(entry.appellation.as_deref(), Some("Bandol"));
+        assert_eq!(entry.region.as_deref(), Some("Provence"));
+        assert_eq!(entry.varietal.as_deref(), Some("Mourvèdre"));
+        assert_eq!(entry.vintage, Some(2022));
+    }
+
+    #[test]
+    fn longest_appellation_wins() {
+        let entry = parse_wine_line("Fontodi Chianti Classico 2020");
+        assert_eq!(entry.appellation.as_deref(), Some("Chianti Classico"));
+        assert_eq!(entry.producer.as_deref(), Some("Fontodi"));
+
+        let entry = parse_wine_line("Jamet Côte-Rôtie 2018");
+        assert_eq!(entry.appellation.as_deref(), Some("Côte-Rôtie"));
+        assert_eq!(entry.varietal.as_deref(), Some("Syrah"));
+    }
+
+    #[test]
+    fn non_vintage_and_vintage_are_told_apart() {
+        let nv = parse_wine_line("Billecart-Salmon Brut Rosé NV Champagne");
+        assert!(nv.non_vintage);
+        assert_eq!(nv.vintage, None);
+        assert_eq!(nv.name, "Billecart-Salmon Brut Rosé");
+        assert_eq!(nv.region.as_deref(), Some("Champagne"));
+
+        let dated = parse_wine_line("Felton Road Pinot Noir 2019 Central Otago");
+        assert!(!dated.non_vintage);
+        assert_eq!(dated.vintage, Some(2019));
+        assert_eq!(dated.varietal.as_deref(), Some("Pinot Noir"));
+        assert_eq!(dated.region.as_deref(), Some("New Zealand"));
+        // Years outside wine-making range aren't vintages
+        assert_eq!(parse_wine_line("Cuvée 1850").vintage, None);
+    }
+
+    #[test]
+    fn words_fold_to_dictionary_spellings() {
+        assert_eq!(fold("Côte-Rôtie,"), "cote rotie");
+        assert_eq!(fold("GRÜNER"), "gruner");
+        assert_eq!(fold("Spätburgunder"), "spatburgunder");
+        assert_eq!(fold("Œil"), "oeil");
+        assert_eq!(fold("(NV)"), "nv");
+    }
+
+    #[test]
+    fn lone_price_is_a_glass_only_under_a_glass_heading() {
+        let sections = wine_sections(parse_menu_text(
+            "BY THE GLASS\nSancerre 2022 14\n\nBOTTLES\nSancerre 2022 52\nRioja Reserva 2016 12 | 48",
+        ));
+        let glass = &sections[0].wines[0];
+        assert_eq!((glass.glass_price, glass.bottle_price), (Some(14.0), None));
+        let bottle = &sections[1].wines[0];
+        assert_eq!((bottle.glass_price, bottle.bottle_price), (None, Some(52.0)));
+        assert_eq!(bottle.varietal.as_deref(), Some("Sauvignon Blanc"));
+        let pair = &sections[1].wines[1];
+        assert_eq!((pair.glass_price, pair.bottle_price), (Some(12.0), Some(48.0)));
+    }
+}
@@ -0,0 +1,84 @@
+// Each test binary uses only some of these helpers
+#![allow(dead_code)]
+
+use serde::Deserialize;
+use table1837_core::{MenuEntry, SearchEngine, SearchItem, WineItem};
+
+#[derive(Deserialize)]
+struct Catalog {
+    cocktails: Vec<SearchItem>,
+    wines: Vec<WineItem>,
+    menu_items: Vec<MenuEntry>,
+}
+
+/// Indexes the fixture bar menu into `engine`
+pub fn catalog(mut engine: SearchEngine) -> SearchEngine {
+    let catalog: Catalog = serde_json::from_str(include_str!("../fixtures/menu.json")).unwrap();
+    engine.index_cocktails(catalog.cocktails);
+    engine.index_wines(catalog.wines);
+    engine.index_menu_items(catalog.menu_items);
+    engine
+}
+
+/// Default engine over the fixture bar menu
+pub fn engine() -> SearchEngine {
+    catalog(SearchEngine::new())
+}
+
+pub fn cocktail(id: &str, name: &str, spirit: &str, ingredients: &[&str]) -> SearchItem {
+    SearchItem {
+        id: id.to_string(),
+        name: name.to_string(),
+        category: "cocktail".to_string(),
+        spirit: Some(spirit.to_string()),
+        ingredients: ingredients.iter().map(|i| i.to_string()).collect(),
+        ..SearchItem::default()
+    }
+}
+
+pub fn wine(id: &str, name: &str, varietal: &str) -> WineItem {
+    WineItem {
+        id: id.to_string(),
+        name: name.to_string(),
+        varietal: Some(varietal.to_string()),
+        ..WineItem::default()
+    }
+}
+
+/// Ids of every plain-text hit, best first
+pub fn ids(engine: &SearchEngine, query: &str) -> Vec<String> {
+    engine
+        .search(query, usize::MAX)
+        .into_iter()
+        .map(|hit| hit.item.id().to_string())
+        .collect()
+}
+
+/// Ids of every hit for a query-language query, best first; panics if the
+/// query doesn't parse
+pub fn query_ids(engine: &SearchEngine, query: &str) -> Vec<String> {
+    engine
+        .search_query(query, usize::MAX)
+        .unwrap_or_else(|e| panic!("query {query:?} failed: {e}"))
+        .into_iter()
+        .map(|hit| hit.item.id().to_string())
+        .collect()
+}
+
+/// Score of `id` for a plain-text query, 0.0 when it isn't found
+pub fn score(engine: &SearchEngine, query: &str, id: &str) -> f64 {
+    engine
+        .search(query, usize::MAX)
+        .into_iter()
+        .find(|hit| hit.item.id() == id)
+        .map_or(0.0, |hit| hit.score)
+}
+
+/// Completion texts for `prefix`, best first
+pub fn texts(engine: &SearchEngine, prefix: &str, limit: usize) -> Vec<String> {
+    engine
+        .autocomplete(prefix, limit)
+        .into_iter()
+        .map(|completion| completion.text)
+        .collect()
+}
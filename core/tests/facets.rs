@@ -0,0 +1,180 @@
+mod common;
+
+use table1837_core::{FacetCount, SearchFilters, SearchRequest, SearchResponse};
+
+fn search(query: &str, filters: SearchFilters) -> SearchResponse {
+    let engine = common::engine();
+    engine
+        .search_filtered(&SearchRequest {
+            query: query.to_string(),
+            filters,
+            limit: None,
+        })
+        .unwrap()
+}
+
+fn ids(response: &SearchResponse) -> Vec<&str> {
+    let mut ids: Vec<&str> = response.hits.iter().map(|hit| hit.item.id()).collect();
+    ids.sort();
+    ids
+}
+
+fn count(facet: &[FacetCount], value: &str) -> usize {
+    facet.iter().find(|f| f.value == value).map_or(0, |f| f.count)
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn empty_query_browses_everything_that_passes_the_filters() {
+    let response = search(
+        "",
+        SearchFilters {
+            spirits: strings(&["Gin"]),
+            ..SearchFilters::default()
+        },
+    );
+    assert_eq!(
+        ids(&response),
+        ["french-75", "gimlet", "gin-and-tonic", "lime-rickey", "negroni"]
+    );
+    assert_eq!(response.total, 5);
+}
+
+#[test]
+fn filters_narrow_a_text_query() {
+    let response = search(
+        "lime",
+        SearchFilters {
+            spirits: strings(&["gin"]),
+            ..SearchFilters::default()
+        },
+    );
+    assert_eq!(ids(&response), ["gimlet", "lime-rickey"]);
+}
+
+#[test]
+fn price_range_matches_any_offered_price() {
+    let cocktails = search(
+        "",
+        SearchFilters {
+            categories: strings(&["cocktail"]),
+            max_price: Some(12.0),
+            ..SearchFilters::default()
+        },
+    );
+    assert_eq!(ids(&cocktails), ["aperol-spritz", "gin-and-tonic", "lime-rickey"]);
+
+    // Wines match on either their glass or bottle price
+    let wines = search(
+        "",
+        SearchFilters {
+            categories: strings(&["wine"]),
+            min_price: Some(20.0),
+            max_price: Some(25.0),
+            ..SearchFilters::default()
+        },
+    );
+    assert_eq!(ids(&wines), ["billecart", "chablis", "napa-cab"]);
+}
+
+#[test]
+fn dietary_tags_accept_keyword_spellings() {
+    let vegan = search(
+        "",
+        SearchFilters {
+            dietary: strings(&["vegan"]),
+            ..SearchFilters::default()
+        },
+    );
+    assert_eq!(ids(&vegan), ["daiquiri", "gin-and-tonic", "paloma"]);
+
+    let both = search(
+        "",
+        SearchFilters {
+            dietary: strings(&["Vegan", "low abv"]),
+            ..SearchFilters::default()
+        },
+    );
+    assert_eq!(ids(&both), ["gin-and-tonic"]);
+
+    let gluten_free = search(
+        "",
+        SearchFilters {
+            dietary: strings(&["gluten-free"]),
+            ..SearchFilters::default()
+        },
+    );
+    assert_eq!(ids(&gluten_free), ["chocolate-torte"]);
+}
+
+#[test]
+fn excluded_ingredients_drop_cocktails_and_dishes() {
+    let gin = search(
+        "gin",
+        SearchFilters {
+            exclude_ingredients: strings(&["lime"]),
+            ..SearchFilters::default()
+        },
+    );
+    assert_eq!(ids(&gin), ["french-75", "gin-and-tonic", "negroni"]);
+
+    let mains = search(
+        "",
+        SearchFilters {
+            categories: strings(&["Mains"]),
+            exclude_ingredients: strings(&["egg"]),
+            ..SearchFilters::default()
+        },
+    );
+    assert_eq!(ids(&mains), ["burger", "salmon"]);
+}
+
+#[test]
+fn each_facet_is_counted_without_its_own_filter() {
+    let response = search(
+        "",
+        SearchFilters {
+            categories: strings(&["cocktail"]),
+            spirits: strings(&["gin"]),
+            ..SearchFilters::default()
+        },
+    );
+    let facets = &response.facets;
+
+    // Every spirit stays selectable while gin is picked
+    assert_eq!(count(&facets.spirits, "gin"), 5);
+    assert_eq!(count(&facets.spirits, "rum"), 1);
+    assert_eq!(count(&facets.spirits, "bourbon"), 3);
+    assert_eq!(facets.spirits[0].value, "gin");
+
+    // Categories are counted among gin drinks only
+    assert_eq!(count(&facets.categories, "cocktail"), 5);
+    assert_eq!(count(&facets.categories, "wine"), 0);
+
+    assert_eq!(count(&facets.dietary, "vegan"), 1);
+    assert_eq!(count(&facets.dietary, "low_abv"), 1);
+}
+
+#[test]
+fn facets_follow_the_text_query() {
+    let response = search("tequila", SearchFilters::default());
+    assert_eq!(response.facets.spirits, [FacetCount { value: "tequila".to_string(), count: 3 }]);
+    assert_eq!(response.total, 3);
+}
+
+#[test]
+fn limit_caps_hits_but_not_total() {
+    let engine = common::engine();
+    let response = engine
+        .search_filtered(&SearchRequest {
+            query: "juice".to_string(),
+            limit: Some(2),
+            ..SearchRequest::default()
+        })
+        .unwrap();
+    assert_eq!(response.hits.len(), 2);
+    assert!(response.total > 2);
+}
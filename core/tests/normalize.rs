@@ -0,0 +1,98 @@
+mod common;
+
+use common::{query_ids, wine};
+use table1837_core::{DomainItem, MenuEntry, SearchEngine, WineItem};
+
+/// Wines and dishes whose text carries accents, ligatures and fractions
+fn accented_list() -> SearchEngine {
+    let wine = |id: &str, name: &str, varietal: &str, region: &str| WineItem {
+        region: Some(region.to_string()),
+        glass_price: Some(16.0),
+        ..wine(id, name, varietal)
+    };
+    let mut engine = SearchEngine::new();
+    engine.index_wines(vec![
+        wine("rose", "Domaine Tempier Rosé", "Mourvèdre", "Bandol"),
+        wine("gewurz", "Trimbach Gewürztraminer", "Gewürztraminer", "Alsace"),
+        wine("gruner", "Hirsch Grüner Veltliner", "Grüner Veltliner", "Kamptal"),
+        wine("macon", "Mâcon-Villages", "Chardonnay", "Bourgogne"),
+        wine("oeil", "Œil de Perdrix", "Pinot Noir", "Neuchâtel"),
+    ]);
+    engine.index_menu_items(vec![
+        MenuEntry {
+            id: "half-dozen".to_string(),
+            name: "½ Dozen Oysters".to_string(),
+            course: "Raw Bar".to_string(),
+            description: "Mignonette, ﬁnger lime".to_string(),
+            ..MenuEntry::default()
+        },
+        MenuEntry {
+            id: "mezcal-pour".to_string(),
+            name: "Mezcal Pour".to_string(),
+            course: "Spirits".to_string(),
+            description: "1½ oz Del Maguey Vida, orange, sal de gusano".to_string(),
+            ..MenuEntry::default()
+        },
+    ]);
+    engine
+}
+
+#[test]
+fn plain_ascii_queries_match_accented_names() {
+    let engine = accented_list();
+    assert_eq!(query_ids(&engine, "rose"), ["rose"]);
+    assert_eq!(query_ids(&engine, "gewurztraminer"), ["gewurz"]);
+    assert_eq!(query_ids(&engine, "gruner veltliner"), ["gruner"]);
+    assert_eq!(query_ids(&engine, "macon"), ["macon"]);
+    assert_eq!(query_ids(&engine, "neuchatel"), ["oeil"]);
+}
+
+#[test]
+fn accented_queries_match_the_same_entries() {
+    let engine = accented_list();
+    assert_eq!(query_ids(&engine, "ROSÉ"), ["rose"]);
+    assert_eq!(query_ids(&engine, "Gewu\u{308}rztraminer"), ["gewurz"]);
+    assert_eq!(query_ids(&engine, "varietal:mourvedre"), ["rose"]);
+}
+
+#[test]
+fn ligatures_and_fractions_fold_to_ascii() {
+    let engine = accented_list();
+    assert_eq!(query_ids(&engine, "oeil"), ["oeil"]);
+    assert_eq!(query_ids(&engine, "\"finger lime\""), ["half-dozen"]);
+    assert_eq!(query_ids(&engine, "\"1/2 dozen\""), ["half-dozen"]);
+}
+
+#[test]
+fn mixed_numbers_keep_the_whole_part_apart() {
+    let engine = accented_list();
+    assert_eq!(query_ids(&engine, "\"1 1/2 oz\""), ["mezcal-pour"]);
+    assert_eq!(query_ids(&engine, "\"1½ oz\""), ["mezcal-pour"]);
+    assert!(query_ids(&engine, "11").is_empty());
+}
+
+#[test]
+fn smart_quotes_and_dashes_read_as_ascii() {
+    let engine = accented_list();
+    assert_eq!(query_ids(&engine, "‘macon villages’"), ["macon"]);
+    assert_eq!(query_ids(&engine, "“grüner veltliner”"), ["gruner"]);
+    assert!(query_ids(&engine, "alsace –gewurztraminer").is_empty());
+    assert_eq!(query_ids(&engine, "price:10–20 category:wine").len(), 5);
+    let error = engine.search_query("gewürz colour:rosé", 5).unwrap_err();
+    assert_eq!(error.position, 7);
+}
+
+#[test]
+fn hits_and_completions_keep_the_original_text() {
+    let engine = accented_list();
+    let hit = &engine.search("rose", 1)[0];
+    let DomainItem::Wine(wine) = &hit.item else {
+        panic!("expected a wine, got {:?}", hit.item);
+    };
+    assert_eq!(wine.name, "Domaine Tempier Rosé");
+    assert_eq!(wine.varietal.as_deref(), Some("Mourvèdre"));
+
+    let completions = engine.autocomplete("gewu", 5);
+    assert_eq!(completions[0].text, "Trimbach Gewürztraminer");
+    assert_eq!(engine.autocomplete("oei", 1)[0].text, "Œil de Perdrix");
+}
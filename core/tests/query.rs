@@ -0,0 +1,117 @@
+mod common;
+
+use common::engine;
+use table1837_core::{QueryError, SearchEngine};
+
+/// Ids of every hit, sorted so assertions don't depend on ranking
+fn ids(engine: &SearchEngine, query: &str) -> Vec<String> {
+    let mut ids = common::query_ids(engine, query);
+    ids.sort();
+    ids
+}
+
+fn error(query: &str) -> QueryError {
+    engine().search_query(query, 10).expect_err(query)
+}
+
+#[test]
+fn guest_phrasing_negates_the_words_after_no() {
+    let engine = engine();
+    let ids = ids(&engine, "something with bourbon but no egg white");
+    assert!(ids.contains(&"old-fashioned".to_string()));
+    assert!(ids.contains(&"boulevardier".to_string()));
+    assert!(!ids.contains(&"whiskey-sour".to_string()));
+}
+
+#[test]
+fn field_prefix_and_price_comparison_combine() {
+    let engine = engine();
+    assert_eq!(ids(&engine, "spirit:rye price:<14"), ["sazerac"]);
+    assert_eq!(ids(&engine, "spirit:rye price:<=16"), ["manhattan", "sazerac"]);
+    assert_eq!(ids(&engine, "price:>=95"), ["barolo", "billecart"]);
+    assert_eq!(ids(&engine, "category:wine price:$20"), ["chablis"]);
+    assert_eq!(ids(&engine, "category:mains price:20-30"), ["burger", "salmon"]);
+}
+
+#[test]
+fn field_prefix_limits_where_a_word_may_match() {
+    let engine = engine();
+    assert_eq!(ids(&engine, "name:lime"), ["lime-rickey"]);
+    // Only the Mezcal Negroni and Boulevardier mention it in their description
+    assert_eq!(ids(&engine, "description:negroni"), ["boulevardier", "mezcal-negroni"]);
+}
+
+#[test]
+fn quoted_phrases_must_appear_in_order() {
+    let engine = engine();
+    assert_eq!(ids(&engine, "\"sweet vermouth\""), ["boulevardier", "manhattan", "mezcal-negroni", "negroni"]);
+    assert!(ids(&engine, "\"vermouth sweet\"").is_empty());
+    // "Grapefruit Juice" and "Lime Juice" are separate ingredients
+    assert!(ids(&engine, "ingredient:\"juice lime\"").is_empty());
+    assert_eq!(ids(&engine, "“egg white”"), ["whiskey-sour"]);
+}
+
+#[test]
+fn minus_excludes_a_term_or_field() {
+    let engine = engine();
+    assert_eq!(ids(&engine, "negroni -mezcal"), ["boulevardier", "negroni"]);
+    assert!(!ids(&engine, "stirred -spirit:gin").contains(&"negroni".to_string()));
+    // Negations aren't typo-corrected
+    assert!(ids(&engine, "pinot -sherry").contains(&"willamette-pinot".to_string()));
+}
+
+#[test]
+fn or_groups_need_one_alternative() {
+    let engine = engine();
+    assert_eq!(
+        ids(&engine, "spirit:mezcal OR spirit:rum | spirit:scotch"),
+        ["daiquiri", "mezcal-negroni", "penicillin"]
+    );
+    assert_eq!(
+        ids(&engine, "price:<12.5 OR diet:vegan"),
+        ["aperol-spritz", "chocolate-torte", "daiquiri", "duck-fat-fries", "gin-and-tonic", "lime-rickey", "paloma"]
+    );
+}
+
+#[test]
+fn plain_queries_rank_as_free_text_search() {
+    let engine = engine();
+    for query in ["spicy margarita", "negroni", "margarta", "lime juice"] {
+        let parsed: Vec<String> = engine
+            .search_query(query, 5)
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.item.id().to_string())
+            .collect();
+        let free: Vec<String> = engine.search(query, 5).into_iter().map(|hit| hit.item.id().to_string()).collect();
+        assert_eq!(parsed, free, "query {query:?}");
+    }
+}
+
+#[test]
+fn malformed_queries_explain_what_is_wrong() {
+    assert_eq!(error("\"egg white").message, "Unclosed quote");
+    assert_eq!(error("\"egg white").position, 0);
+    assert_eq!(error("gin colour:red").message, "Unknown field \"colour\"");
+    assert_eq!(error("gin colour:red").position, 4);
+    assert_eq!(error("price:<abc").message, "Invalid price \"<abc\"");
+    assert_eq!(error("price:<abc").position, 6);
+    assert_eq!(error("price:20-10").message, "Price range \"20-10\" is backwards");
+    assert_eq!(error("spirit:").message, "Missing value after \"spirit:\"");
+    assert_eq!(error("diet:carnivore").message, "Unknown dietary tag \"carnivore\"");
+    assert_eq!(error("gin OR").message, "\"OR\" needs a term on each side");
+    assert_eq!(error("OR gin").message, "\"OR\" needs a term on each side");
+    assert_eq!(error("gin OR OR rum").position, 7);
+    assert_eq!(error("tequila no").message, "Nothing to exclude after \"no\"");
+    assert_eq!(error("-\"\"").message, "Nothing to exclude after \"-\"");
+    assert_eq!(error("gin colour:red").to_string(), "Unknown field \"colour\" (at character 4)");
+}
+
+#[test]
+fn plain_search_never_rejects_a_query() {
+    let engine = engine();
+    for query in ["\"negroni", "negroni colour:red", "negroni OR", "negroni price:<abc"] {
+        assert!(engine.search_query(query, 5).is_err(), "query {query:?}");
+        assert_eq!(engine.search(query, 5)[0].item.id(), "negroni", "query {query:?}");
+    }
+}
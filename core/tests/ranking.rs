@@ -0,0 +1,100 @@
+mod common;
+
+use common::score;
+use table1837_core::{RankingConfig, SearchEngine};
+
+fn engine_with(ranking: RankingConfig) -> SearchEngine {
+    common::catalog(SearchEngine::with_ranking(ranking))
+}
+
+fn engine() -> SearchEngine {
+    engine_with(RankingConfig::default())
+}
+
+fn top_ids(engine: &SearchEngine, query: &str, limit: usize) -> Vec<String> {
+    engine
+        .search(query, limit)
+        .into_iter()
+        .map(|hit| hit.item.id().to_string())
+        .collect()
+}
+
+/// Queries guests and staff actually type, with the entry that should come
+/// back first
+const QUERIES: &[(&str, &str)] = &[
+    ("negroni", "negroni"),
+    ("mezcal", "mezcal-negroni"),
+    ("old fashioned", "old-fashioned"),
+    ("spicy margarita", "spicy-margarita"),
+    ("margarita", "margarita"),
+    ("espresso", "espresso-martini"),
+    ("gin tonic", "gin-and-tonic"),
+    ("grapefruit juice", "paloma"),
+    ("scotch", "penicillin"),
+    ("whiskey sour", "whiskey-sour"),
+    ("pinot noir", "willamette-pinot"),
+    ("burgundy", "chablis"),
+    ("riesling", "donnhoff"),
+    ("barolo", "barolo"),
+    ("burger", "burger"),
+    ("fries", "duck-fat-fries"),
+    ("oysters", "oysters"),
+    ("chocolate", "chocolate-torte"),
+];
+
+#[test]
+fn fixture_queries_rank_the_expected_entry_first() {
+    let engine = engine();
+    for &(query, id) in QUERIES {
+        let ids = top_ids(&engine, query, 3);
+        assert_eq!(ids.first().map(String::as_str), Some(id), "query {query:?} returned {ids:?}");
+    }
+}
+
+#[test]
+fn name_match_outranks_repeated_ingredient_matches() {
+    // The Gimlet lists lime three times; the Rickey is named for it
+    let engine = engine();
+    assert!(score(&engine, "lime", "lime-rickey") > score(&engine, "lime", "gimlet"));
+}
+
+#[test]
+fn common_words_weigh_less_than_rare_ones() {
+    let engine = engine();
+    assert!(score(&engine, "juice", "paloma") < score(&engine, "grapefruit", "paloma"));
+    assert!(score(&engine, "syrup", "daiquiri") < score(&engine, "rum", "daiquiri"));
+}
+
+#[test]
+fn closer_name_matches_rank_above_longer_ones() {
+    let engine = engine();
+    assert_eq!(top_ids(&engine, "negroni", 2), ["negroni", "mezcal-negroni"]);
+    assert_eq!(top_ids(&engine, "spicy margarita", 2), ["spicy-margarita", "margarita"]);
+}
+
+#[test]
+fn shared_ingredient_finds_every_drink_using_it() {
+    let mut ids = top_ids(&engine(), "campari", 10);
+    ids.sort();
+    assert_eq!(ids, ["boulevardier", "mezcal-negroni", "negroni"]);
+}
+
+#[test]
+fn field_boosts_are_configurable() {
+    let mut ranking = RankingConfig::default();
+    ranking.boosts.name = 0.1;
+    ranking.boosts.description = 10.0;
+    let engine = engine_with(ranking);
+    assert_eq!(top_ids(&engine, "fries", 1), ["steak-frites"]);
+}
+
+#[test]
+fn limited_results_are_the_head_of_the_full_ranking() {
+    let engine = engine();
+    for query in ["lime juice", "bitter stirred", "dairy", "lemon"] {
+        let all = top_ids(&engine, query, usize::MAX);
+        assert!(all.len() > 3, "query {query:?}");
+        assert_eq!(top_ids(&engine, query, 3), all[..3], "query {query:?}");
+    }
+    assert!(engine.search("negroni", 0).is_empty());
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::{cocktail, ids, wine};
+use table1837_core::{MenuEntry, SearchDomain, SearchEngine, WineItem};
+
+/// A few cocktails, two wines and a dish
+fn small_bar() -> SearchEngine {
+    let mut engine = SearchEngine::new();
+    engine.index_cocktails(vec![
+        cocktail("negroni", "Negroni", "gin", &["Gin", "Campari", "Sweet Vermouth"]),
+        cocktail("boulevardier", "Boulevardier", "bourbon", &["Bourbon", "Campari", "Sweet Vermouth"]),
+        cocktail("paloma", "Paloma", "tequila", &["Tequila", "Grapefruit Soda", "Lime"]),
+        cocktail("margarita", "Margarita", "tequila", &["Tequila", "Cointreau", "Lime"]),
+    ]);
+    engine.index_wines(vec![
+        WineItem {
+            producer: Some("Domaine Drouhin".to_string()),
+            region: Some("Willamette Valley".to_string()),
+            vintage: Some(2019),
+            ..wine("willamette-pinot", "Estate Pinot Noir", "Pinot Noir")
+        },
+        WineItem {
+            producer: Some("Frog's Leap".to_string()),
+            region: Some("Napa Valley".to_string()),
+            vintage: Some(2018),
+            ..wine("napa-cab", "Napa Valley Cabernet Sauvignon", "Cabernet Sauvignon")
+        },
+    ]);
+    engine.index_menu_items(vec![MenuEntry {
+        id: "burger".to_string(),
+        name: "Table Burger".to_string(),
+        course: "Mains".to_string(),
+        description: "dry-aged beef, gruyère, brioche".to_string(),
+        allergens: vec!["dairy".to_string(), "gluten".to_string()],
+        ..MenuEntry::default()
+    }]);
+    engine
+}
+
+#[test]
+fn name_match_ranks_first() {
+    let hits = small_bar().search("negroni", 10);
+    assert_eq!(hits[0].item.id(), "negroni");
+    assert!(hits.windows(2).all(|pair| pair[0].score >= pair[1].score));
+}
+
+#[test]
+fn ingredient_match_finds_every_cocktail_using_it() {
+    let mut found = ids(&small_bar(), "campari");
+    found.sort();
+    assert_eq!(found, ["boulevardier", "negroni"]);
+}
+
+#[test]
+fn limit_caps_results() {
+    assert_eq!(small_bar().search("sweet vermouth", 1).len(), 1);
+}
+
+#[test]
+fn unmatched_query_returns_nothing() {
+    assert!(small_bar().search("mezcal", 10).is_empty());
+}
+
+#[test]
+fn ingredient_lookup_ignores_case() {
+    let ids: Vec<String> = small_bar()
+        .search_by_ingredient("COINTREAU")
+        .into_iter()
+        .map(|item| item.id)
+        .collect();
+    assert_eq!(ids, ["margarita"]);
+}
+
+#[test]
+fn unified_search_tags_each_hit_with_its_domain() {
+    let bar = small_bar();
+    let pinot = bar.search("pinot", 10);
+    assert_eq!(pinot.len(), 1);
+    assert_eq!(pinot[0].item.domain(), SearchDomain::Wine);
+
+    let burger = bar.search("burger", 10);
+    assert_eq!(burger.len(), 1);
+    assert_eq!(burger[0].item.domain(), SearchDomain::MenuItem);
+}
+
+#[test]
+fn wine_fields_are_searchable() {
+    let bar = small_bar();
+    for query in ["willamette", "drouhin", "pinot 2019"] {
+        let hits = bar.search(query, 10);
+        assert_eq!(hits.len(), 1, "query {query:?}");
+        assert_eq!(hits[0].item.id(), "willamette-pinot");
+    }
+}
+
+#[test]
+fn hits_serialize_with_domain_alongside_item() {
+    let hits = small_bar().search("burger", 1);
+    let json = serde_json::to_value(&hits[0]).unwrap();
+    assert_eq!(json["domain"], "menu_item");
+    assert_eq!(json["item"]["course"], "Mains");
+    assert!(json["score"].as_f64().unwrap() > 0.0);
+}
+
+#[test]
+fn typos_find_the_intended_item() {
+    let bar = small_bar();
+    for (query, id) in [
+        ("margarta", "margarita"),
+        ("negorni", "negroni"),
+        ("cabernet savignon", "napa-cab"),
+    ] {
+        let hits = bar.search(query, 10);
+        assert_eq!(hits.first().map(|hit| hit.item.id()), Some(id), "query {query:?}");
+    }
+}
+
+#[test]
+fn corrected_match_scores_below_exact_match() {
+    let bar = small_bar();
+    let exact = bar.search("negroni", 1)[0].score;
+    let typo = bar.search("negorni", 1)[0].score;
+    assert!(typo < exact);
+}
+
+#[test]
+fn short_tokens_are_not_fuzzed() {
+    assert!(small_bar().search("nig", 10).is_empty());
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::{engine, texts};
+use table1837_core::{CompletionKind, SearchRequest};
+
+#[test]
+fn completes_names_from_any_word() {
+    let engine = engine();
+    assert_eq!(texts(&engine, "marg", 5), ["Margarita", "Spicy Margarita"]);
+    assert_eq!(texts(&engine, "Negr", 5), ["Negroni", "Mezcal Negroni"]);
+}
+
+#[test]
+fn common_spirits_and_ingredients_rank_first() {
+    let engine = engine();
+    let completions = engine.autocomplete("gi", 3);
+    assert_eq!(completions[0].text, "gin");
+    assert_eq!(completions[0].kind, CompletionKind::Spirit);
+    assert_eq!(completions[0].count, 5);
+    // Gin as an ingredient is the same text and isn't offered twice
+    assert_eq!(completions.iter().filter(|c| c.text.eq_ignore_ascii_case("gin")).count(), 1);
+    assert!(completions.windows(2).all(|pair| pair[0].score >= pair[1].score));
+}
+
+#[test]
+fn multi_word_prefixes_complete_the_last_word() {
+    let engine = engine();
+    assert_eq!(texts(&engine, "sweet ver", 5), ["Sweet Vermouth"]);
+    assert_eq!(texts(&engine, "lime j", 5), ["Lime Juice"]);
+    let completion = &engine.autocomplete("sweet ver", 1)[0];
+    assert_eq!(completion.kind, CompletionKind::Ingredient);
+    assert_eq!(completion.count, 4);
+}
+
+#[test]
+fn unknown_or_empty_prefixes_complete_nothing() {
+    let engine = engine();
+    assert!(engine.autocomplete("xyz", 5).is_empty());
+    assert!(engine.autocomplete("  ", 5).is_empty());
+    assert_eq!(engine.autocomplete("s", 2).len(), 2);
+}
+
+#[test]
+fn did_you_mean_respells_queries_that_find_nothing() {
+    let engine = engine();
+    // Too far off for search's own typo correction
+    assert!(engine.search("negorny", 5).is_empty());
+    assert_eq!(engine.did_you_mean("negorny").as_deref(), Some("negroni"));
+    assert_eq!(engine.did_you_mean("Daikiri").as_deref(), Some("daiquiri"));
+    assert_eq!(engine.did_you_mean("Sazzerakk daikiri").as_deref(), Some("sazerac daiquiri"));
+}
+
+#[test]
+fn did_you_mean_stays_quiet_when_there_is_nothing_better() {
+    let engine = engine();
+    assert_eq!(engine.did_you_mean("negroni"), None);
+    assert_eq!(engine.did_you_mean("xylophone"), None);
+}
+
+#[test]
+fn empty_filtered_results_carry_a_suggestion() {
+    let engine = engine();
+    let response = engine
+        .search_filtered(&SearchRequest {
+            query: "negorny".to_string(),
+            ..SearchRequest::default()
+        })
+        .unwrap();
+    assert_eq!(response.total, 0);
+    assert_eq!(response.did_you_mean.as_deref(), Some("negroni"));
+
+    let found = engine
+        .search_filtered(&SearchRequest {
+            query: "margarita".to_string(),
+            ..SearchRequest::default()
+        })
+        .unwrap();
+    assert_eq!(found.did_you_mean, None);
+}
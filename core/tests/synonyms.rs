@@ -0,0 +1,86 @@
+mod common;
+
+use common::{engine, ids, score, wine};
+use table1837_core::{SearchEngine, SynonymConfig};
+
+fn contains_all(ids: &[String], expected: &[&str]) -> bool {
+    expected.iter().all(|id| ids.iter().any(|found| found == id))
+}
+
+fn wine_list() -> SearchEngine {
+    let mut engine = SearchEngine::new();
+    engine.index_wines(vec![
+        wine("shiraz", "Estate Shiraz", "Shiraz"),
+        wine("syrah", "Estate Syrah", "Syrah"),
+        wine("pinot-gris", "Alsace Pinot Gris", "Pinot Gris"),
+        wine("pinot-noir", "Alsace Pinot Noir", "Pinot Noir"),
+    ]);
+    engine
+}
+
+#[test]
+fn broader_spirits_find_every_kind() {
+    let engine = engine();
+    let whiskey = ids(&engine, "whiskey");
+    assert!(contains_all(&whiskey, &["boulevardier", "old-fashioned", "manhattan", "penicillin", "whiskey-sour"]));
+    assert!(!whiskey.contains(&"negroni".to_string()));
+
+    // Spelling variants reach them too, through "whiskey"
+    assert!(contains_all(&ids(&engine, "whisky"), &["boulevardier", "penicillin"]));
+}
+
+#[test]
+fn abbreviations_and_slang_expand() {
+    let engine = engine();
+    assert_eq!(ids(&engine, "marg")[..2], ["margarita", "spicy-margarita"]);
+    assert!(contains_all(&ids(&engine, "bubbles"), &["billecart", "french-75", "aperol-spritz"]));
+    assert_eq!(ids(&engine, "g&t")[0], "gin-and-tonic");
+}
+
+#[test]
+fn grape_synonyms_match_either_name() {
+    let engine = wine_list();
+    assert_eq!(ids(&engine, "syrah"), ["syrah", "shiraz"]);
+    assert_eq!(ids(&engine, "pinot grigio")[0], "pinot-gris");
+    assert_eq!(ids(&engine, "pinot gris")[0], "pinot-gris");
+}
+
+#[test]
+fn synonym_hits_score_below_exact_hits() {
+    let wines = wine_list();
+    let exact = score(&wines, "shiraz", "shiraz");
+    let synonym = score(&wines, "shiraz", "syrah");
+    assert!(synonym > 0.0 && synonym < exact);
+    assert!((synonym - exact * wines.synonyms().synonym_weight).abs() < 1e-9);
+
+    let catalog = engine();
+    assert!(score(&catalog, "whiskey", "boulevardier") < score(&catalog, "whiskey", "whiskey-sour"));
+}
+
+#[test]
+fn query_language_expands_bare_and_field_scoped_words() {
+    let engine = engine();
+    let ids = |query: &str| -> Vec<String> {
+        let mut ids = common::query_ids(&engine, query);
+        ids.sort();
+        ids
+    };
+    assert_eq!(ids("spirit:agave price:<14"), ["margarita", "paloma"]);
+    assert_eq!(ids("spirit:whiskey -bourbon"), ["manhattan", "penicillin", "sazerac"]);
+    // Quoted terms stay literal
+    assert!(ids("\"bubbles\"").is_empty());
+}
+
+#[test]
+fn dictionary_is_configurable() {
+    let mut engine = engine();
+    assert!(ids(&engine, "tini").is_empty());
+
+    engine.set_synonyms(SynonymConfig {
+        synonyms: vec![vec!["martini".to_string(), "tini".to_string()]],
+        hypernyms: Default::default(),
+        ..SynonymConfig::default()
+    });
+    assert_eq!(ids(&engine, "tini"), ["espresso-martini"]);
+    assert!(!ids(&engine, "whiskey").contains(&"boulevardier".to_string()));
+}